@@ -0,0 +1,106 @@
+use reqwest::{header, Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use uri::format_uri;
+use uuid::Uuid;
+
+use crate::{ApiThing, ApiThingData, ClientError, HealthResponse, RootResponse};
+
+/// Client of the REST API of the server, with a method for each route
+///
+/// ```ignore
+/// let client = ApiClient::new("http://localhost:6000");
+/// let thing = client.get_thing(id).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_client(base_url, reqwest::Client::new())
+    }
+
+    /// Uses the given client, e.g. one with timeouts or default headers
+    pub fn with_client(base_url: impl Into<String>, client: reqwest::Client) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        ApiClient { base_url, client }
+    }
+
+    /// `GET /`
+    pub async fn get_root(&self) -> Result<RootResponse, ClientError> {
+        self.send(self.request(Method::GET, "/")).await
+    }
+
+    /// `GET /health/live`
+    pub async fn get_live(&self) -> Result<HealthResponse, ClientError> {
+        self.send(self.request(Method::GET, "/health/live")).await
+    }
+
+    /// `GET /health/ready`. A server that is not ready answers with `503 Service
+    /// Unavailable` and the checks that failed, which is returned like a ready one.
+    pub async fn get_ready(&self) -> Result<HealthResponse, ClientError> {
+        let request = self.request(Method::GET, "/health/ready");
+        self.send_accepting(request, StatusCode::SERVICE_UNAVAILABLE)
+            .await
+    }
+
+    /// `POST /things`
+    pub async fn create_thing(&self, data: &ApiThingData) -> Result<ApiThing, ClientError> {
+        let request = self
+            .request(Method::POST, "/things")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(data)?);
+        self.send(request).await
+    }
+
+    /// `GET /things/{thing_id}`
+    pub async fn get_thing(&self, thing_id: Uuid) -> Result<ApiThing, ClientError> {
+        let path = format_uri!("/things/{thing_id}");
+        self.send(self.request(Method::GET, &path)).await
+    }
+
+    /// Like [`ApiClient::get_thing`], but a thing that doesn't exist is `None`
+    pub async fn find_thing(&self, thing_id: Uuid) -> Result<Option<ApiThing>, ClientError> {
+        match self.get_thing(thing_id).await {
+            Err(e) if e.is_not_found() => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    /// `DELETE /things/{thing_id}`
+    pub async fn delete_thing(&self, thing_id: Uuid) -> Result<(), ClientError> {
+        let path = format_uri!("/things/{thing_id}");
+        self.send(self.request(Method::DELETE, &path)).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{path}", self.base_url))
+            .header(header::ACCEPT, "application/json")
+    }
+
+    /// Sends the request and reads the response as `T`. An empty response is read as
+    /// JSON `null`, so that it can be read as `()`.
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        self.send_accepting(request, StatusCode::OK).await
+    }
+
+    /// Like [`ApiClient::send`], but also reads the body of a response with the given
+    /// error status as `T`
+    async fn send_accepting<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        accepted: StatusCode,
+    ) -> Result<T, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() && status != accepted {
+            return Err(ClientError::from_response(status, &body));
+        }
+        let body: &[u8] = if body.is_empty() { b"null" } else { &body };
+        Ok(serde_json::from_slice(body)?)
+    }
+}
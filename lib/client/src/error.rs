@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// The `error` of an error response of the API
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    NotFound,
+    InvalidPathParam,
+    InternalServerError,
+    /// An error this version of the client doesn't know, or a response that isn't an
+    /// error of the API at all (e.g. from a proxy)
+    #[serde(other)]
+    Unknown,
+}
+
+impl ApiErrorCode {
+    /// The codes the API returns
+    pub const ALL: [ApiErrorCode; 3] = [
+        ApiErrorCode::NotFound,
+        ApiErrorCode::InvalidPathParam,
+        ApiErrorCode::InternalServerError,
+    ];
+}
+
+// Written out, so that the schema leaves out `Unknown`
+#[cfg(feature = "openapi")]
+impl utoipa::PartialSchema for ApiErrorCode {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        let codes = ApiErrorCode::ALL.map(|code| serde_json::to_value(code).unwrap());
+        utoipa::openapi::ObjectBuilder::new()
+            .schema_type(utoipa::openapi::schema::Type::String)
+            .enum_values(Some(codes))
+            .into()
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl utoipa::ToSchema for ApiErrorCode {}
+
+/// The body of an error response of the API
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiErrorBody {
+    pub error: ApiErrorCode,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The API answered with an error status
+    Api {
+        status: StatusCode,
+        code: ApiErrorCode,
+    },
+    /// The request could not be sent or its response could not be read
+    Request(reqwest::Error),
+    /// The response is not what the API returns for the request
+    InvalidResponse(serde_json::Error),
+}
+
+impl ClientError {
+    pub fn code(&self) -> Option<ApiErrorCode> {
+        match self {
+            ClientError::Api { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.code() == Some(ApiErrorCode::NotFound)
+    }
+
+    /// Reads the error from the body of a response with an error status
+    pub(crate) fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        let code = serde_json::from_slice::<ApiErrorBody>(body)
+            .map_or(ApiErrorCode::Unknown, |body| body.error);
+        ClientError::Api { status, code }
+    }
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Api { status, code } => write!(f, "API error {status}: {code:?}"),
+            ClientError::Request(e) => write!(f, "Request failed: {e}"),
+            ClientError::InvalidResponse(e) => write!(f, "Invalid response: {e}"),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Api { .. } => None,
+            ClientError::Request(e) => Some(e),
+            ClientError::InvalidResponse(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Request(e)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> Self {
+        ClientError::InvalidResponse(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_from_response() {
+        let error = ClientError::from_response(StatusCode::NOT_FOUND, br#"{"error":"not_found"}"#);
+        assert!(error.is_not_found());
+        assert_eq!(error.to_string(), "API error 404 Not Found: NotFound");
+    }
+
+    #[test]
+    fn test_unknown_errors() {
+        let new_code = ClientError::from_response(StatusCode::CONFLICT, br#"{"error":"conflict"}"#);
+        assert_eq!(new_code.code(), Some(ApiErrorCode::Unknown));
+        let panic = ClientError::from_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            br#"{"error":{"kind":"panic"}}"#,
+        );
+        assert_eq!(panic.code(), Some(ApiErrorCode::Unknown));
+        let proxy = ClientError::from_response(StatusCode::BAD_GATEWAY, b"<html>");
+        assert_eq!(proxy.code(), Some(ApiErrorCode::Unknown));
+    }
+}
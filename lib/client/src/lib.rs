@@ -0,0 +1,7 @@
+mod client;
+mod error;
+mod models;
+
+pub use client::*;
+pub use error::*;
+pub use models::*;
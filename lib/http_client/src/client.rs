@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{header, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{HttpClientError, HttpClientSettings};
+
+/// At most this much of an error response is included in the error
+const MAX_ERROR_BODY_LENGTH: usize = 500;
+
+/// Headers passed on with each request, such as the id of the request being handled
+pub trait PropagatedHeaders {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)>;
+}
+
+/// JSON client for one service. Paths are appended to the base URL of the service and
+/// are best built with `format_uri!`, which encodes the variables in them:
+///
+/// ```ignore
+/// let client = ctx.env().http_clients.get("things")?;
+/// let thing: Thing = client.get(&ctx, &format_uri!("/things/{id}")).await?;
+/// ```
+///
+/// Each request carries the [`PropagatedHeaders`] of the caller, such as the request id
+/// of a context. Requests with idempotent methods are retried as configured, and
+/// everything but a successful response with a valid body is an error.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    name: String,
+    settings: HttpClientSettings,
+    client: reqwest::Client,
+}
+
+impl HttpClient {
+    pub fn new(name: &str, settings: &HttpClientSettings) -> Result<Self, HttpClientError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(settings.timeout_ms))
+            .connect_timeout(Duration::from_millis(settings.connect_timeout_ms))
+            .build()
+            .map_err(HttpClientError::Build)?;
+        Ok(HttpClient {
+            name: name.to_string(),
+            settings: settings.clone(),
+            client,
+        })
+    }
+
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+    ) -> Result<T, HttpClientError> {
+        self.send(headers, Method::GET, path, None::<&()>).await
+    }
+
+    /// Like [`HttpClient::get`], but a 404 response is `None`
+    pub async fn get_optional<T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+    ) -> Result<Option<T>, HttpClientError> {
+        let response = self.execute(headers, Method::GET, path, None).await?;
+        if response.status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        response.json().map(Some)
+    }
+
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+        body: &B,
+    ) -> Result<T, HttpClientError> {
+        self.send(headers, Method::POST, path, Some(body)).await
+    }
+
+    pub async fn put<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+        body: &B,
+    ) -> Result<T, HttpClientError> {
+        self.send(headers, Method::PUT, path, Some(body)).await
+    }
+
+    pub async fn delete<T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+    ) -> Result<T, HttpClientError> {
+        self.send(headers, Method::DELETE, path, None::<&()>).await
+    }
+
+    /// Sends the body as JSON and reads the response as `T`. An empty response is read
+    /// as JSON `null`, so it can be read as `()` or an `Option`.
+    pub async fn send<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        headers: &impl PropagatedHeaders,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<T, HttpClientError> {
+        let body = body
+            .map(serde_json::to_vec)
+            .transpose()
+            .map_err(HttpClientError::Body)?;
+        self.execute(headers, method, path, body).await?.json()
+    }
+
+    /// Sends a single `GET` request, without retries, and fails unless the response has
+    /// a success status. The body is ignored.
+    pub async fn probe(
+        &self,
+        headers: &impl PropagatedHeaders,
+        path: &str,
+    ) -> Result<(), HttpClientError> {
+        let method = Method::GET;
+        let url = self.url(path);
+        let (status, body) =
+            self.attempt(headers, &method, &url, None)
+                .await
+                .map_err(|source| HttpClientError::Request {
+                    method: method.clone(),
+                    url: url.clone(),
+                    source,
+                })?;
+        HttpResponse {
+            method,
+            url,
+            status,
+            body,
+        }
+        .error_for_status()
+        .map(|_| ())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.settings.base_url.trim_end_matches('/'))
+    }
+
+    /// Sends the request, retrying it if it may be, and returns the last response
+    async fn execute(
+        &self,
+        headers: &impl PropagatedHeaders,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let url = self.url(path);
+        let retries = if method.is_idempotent() {
+            self.settings.max_retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            debug!(client = self.name, %method, url, attempt, "Sending request");
+            let result = self.attempt(headers, &method, &url, body.clone()).await;
+            let retry = match &result {
+                Ok((status, _)) => matches!(
+                    *status,
+                    StatusCode::BAD_GATEWAY
+                        | StatusCode::SERVICE_UNAVAILABLE
+                        | StatusCode::GATEWAY_TIMEOUT
+                ),
+                Err(e) => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+            };
+            if retry && attempt < retries {
+                let delay = self.settings.retry_backoff_ms << attempt.min(16);
+                match &result {
+                    Ok((status, _)) => warn!("{method} {url} returned {status}, retrying"),
+                    Err(e) => warn!("{method} {url} failed, retrying: {e}"),
+                }
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                attempt += 1;
+                continue;
+            }
+            let (status, body) = match result {
+                Ok(response) => response,
+                Err(source) => {
+                    return Err(HttpClientError::Request {
+                        method,
+                        url,
+                        source,
+                    })
+                }
+            };
+            return Ok(HttpResponse {
+                method,
+                url,
+                status,
+                body,
+            });
+        }
+    }
+
+    async fn attempt(
+        &self,
+        headers: &impl PropagatedHeaders,
+        method: &Method,
+        url: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<(StatusCode, Bytes), reqwest::Error> {
+        let mut request = self
+            .client
+            .request(method.clone(), url)
+            .header(header::ACCEPT, "application/json");
+        for (name, value) in headers.propagated_headers() {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        Ok((status, response.bytes().await?))
+    }
+}
+
+struct HttpResponse {
+    method: Method,
+    url: String,
+    status: StatusCode,
+    body: Bytes,
+}
+
+impl HttpResponse {
+    fn error_for_status(self) -> Result<Self, HttpClientError> {
+        if self.status.is_success() {
+            return Ok(self);
+        }
+        let body = String::from_utf8_lossy(&self.body);
+        let body = match body.char_indices().nth(MAX_ERROR_BODY_LENGTH) {
+            Some((end, _)) => format!("{}...", &body[..end]),
+            None => body.to_string(),
+        };
+        Err(HttpClientError::Status {
+            method: self.method,
+            url: self.url,
+            status: self.status,
+            body,
+        })
+    }
+
+    fn json<T: DeserializeOwned>(self) -> Result<T, HttpClientError> {
+        let HttpResponse {
+            method, url, body, ..
+        } = self.error_for_status()?;
+        let body = if body.is_empty() {
+            Bytes::from_static(b"null")
+        } else {
+            body
+        };
+        serde_json::from_slice(&body).map_err(|source| HttpClientError::InvalidResponse {
+            method,
+            url,
+            source,
+        })
+    }
+}
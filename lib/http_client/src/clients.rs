@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::{HttpClient, HttpClientError, HttpClientSettings};
+
+/// The clients of all configured services, by name
+#[derive(Debug, Clone, Default)]
+pub struct HttpClients(Arc<BTreeMap<String, HttpClient>>);
+
+impl HttpClients {
+    pub fn init(settings: &BTreeMap<String, HttpClientSettings>) -> Result<Self, HttpClientError> {
+        let clients = settings
+            .iter()
+            .map(|(name, settings)| Ok((name.clone(), HttpClient::new(name, settings)?)))
+            .collect::<Result<_, HttpClientError>>()?;
+        Ok(HttpClients(Arc::new(clients)))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&HttpClient, HttpClientError> {
+        self.0
+            .get(name)
+            .ok_or_else(|| HttpClientError::NotConfigured(name.to_string()))
+    }
+}
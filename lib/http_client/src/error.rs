@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use http::{Method, StatusCode};
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    /// The client could not be built from its settings
+    Build(reqwest::Error),
+    /// There are no settings for a client with the name
+    NotConfigured(String),
+    /// The request body could not be serialized
+    Body(serde_json::Error),
+    /// The request could not be sent or its response could not be read
+    Request {
+        method: Method,
+        url: String,
+        source: reqwest::Error,
+    },
+    /// The service answered with an error status. The body is truncated.
+    Status {
+        method: Method,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    /// The response body is not valid JSON for the expected type
+    InvalidResponse {
+        method: Method,
+        url: String,
+        source: serde_json::Error,
+    },
+}
+
+impl Display for HttpClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpClientError::Build(e) => write!(f, "Invalid HTTP client settings: {e}"),
+            HttpClientError::NotConfigured(name) => {
+                write!(f, "No HTTP client configured for {name}")
+            }
+            HttpClientError::Body(e) => write!(f, "Invalid request body: {e}"),
+            HttpClientError::Request {
+                method,
+                url,
+                source,
+            } => write!(f, "{method} {url} failed: {source}"),
+            HttpClientError::Status {
+                method,
+                url,
+                status,
+                body,
+            } => write!(f, "{method} {url} returned {status}: {body}"),
+            HttpClientError::InvalidResponse {
+                method,
+                url,
+                source,
+            } => write!(f, "Invalid response to {method} {url}: {source}"),
+        }
+    }
+}
+
+impl Error for HttpClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpClientError::Build(e) => Some(e),
+            HttpClientError::Body(e) => Some(e),
+            HttpClientError::Request { source, .. } => Some(source),
+            HttpClientError::InvalidResponse { source, .. } => Some(source),
+            HttpClientError::NotConfigured(_) | HttpClientError::Status { .. } => None,
+        }
+    }
+}
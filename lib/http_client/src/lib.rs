@@ -0,0 +1,9 @@
+mod client;
+mod clients;
+mod error;
+mod settings;
+
+pub use client::*;
+pub use clients::*;
+pub use error::*;
+pub use settings::*;
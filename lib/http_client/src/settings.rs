@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpClientSettings {
+    /// Prepended to the path of each request
+    pub base_url: String,
+    /// Timeout of each attempt, including reading the response
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// How often requests with idempotent methods are retried after connection errors,
+    /// timeouts and 502, 503 or 504 responses
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each following one
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Path that answers with a success status while the service is up. Services with
+    /// one are checked before the server reports itself ready.
+    #[serde(default)]
+    pub health_path: Option<String>,
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    2_000
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
+}
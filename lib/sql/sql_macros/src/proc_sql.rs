@@ -5,7 +5,6 @@ use quote::quote;
 use regex::Regex;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::spanned::Spanned;
 use syn::{parse2, Error, Expr, LitStr, Token};
 
 struct Assignment {
@@ -59,7 +58,7 @@ fn build_lookup_map(assignments: Vec<Assignment>) -> Result<BTreeMap<String, Ass
         let name = assignment.name.to_string();
         if lookup.contains_key(&name) {
             return Err(Error::new(
-                name.span(),
+                assignment.name.span(),
                 format!("duplicate bindings for \"{name}\""),
             ));
         }
@@ -83,7 +82,7 @@ fn try_proc_sql(input: TokenStream) -> Result<TokenStream, Error> {
     // parsed template string and binding assignments
     let mut statements: Vec<TokenStream> = vec![
         // Define builder that can be used to build the SQL instance
-        quote! { sqlx::QueryBuilder::new("") },
+        quote! { sql::SqlBuilder::new() },
     ];
     let re = Regex::new(BINDING_RE).unwrap();
     // Start offset for binding search
@@ -172,7 +171,7 @@ mod tests {
         assert_eq!(
             stringify(proc_sql(quote! {"SELECT * FROM things"})),
             stringify(quote! {
-                sqlx::QueryBuilder::new("")
+                sql::SqlBuilder::new()
                     .push("SELECT * FROM things")
                     .build()
             })
@@ -186,7 +185,7 @@ mod tests {
                 quote! {"INSERT INTO things (name, description) VALUES (${name}, ${description})"}
             )),
             stringify(quote! {
-                sqlx::QueryBuilder::new("")
+                sql::SqlBuilder::new()
                     .push("INSERT INTO things (name, description) VALUES (")
                     .push_bind(name)
                     .push(", ")
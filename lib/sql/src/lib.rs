@@ -1,6 +1,8 @@
 extern crate sql_macros;
 
 mod encode;
+mod query;
 
 pub use encode::*;
+pub use query::*;
 pub use sql_macros::*;
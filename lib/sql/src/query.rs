@@ -0,0 +1,117 @@
+#[cfg(feature = "bound_values")]
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArguments, PgStatement};
+use sqlx::query::Query;
+use sqlx::{Encode, Execute, IntoArguments, Postgres, QueryBuilder, Type};
+
+/// Builds the query of [`sql!`](crate::sql). Like [`QueryBuilder`], but with the
+/// `bound_values` feature it also keeps a description of each bound value, so that they
+/// can be recorded in tests.
+pub struct SqlBuilder<'args> {
+    builder: QueryBuilder<'args, Postgres>,
+    #[cfg(feature = "bound_values")]
+    bound_values: Vec<String>,
+}
+
+/// A value that can be bound in [`sql!`](crate::sql). With the `bound_values` feature,
+/// which only tests should enable, that is every value with a `Debug` representation.
+#[cfg(feature = "bound_values")]
+pub trait BindValue: Debug {}
+
+#[cfg(feature = "bound_values")]
+impl<T: Debug> BindValue for T {}
+
+/// A value that can be bound in [`sql!`](crate::sql). With the `bound_values` feature,
+/// which only tests should enable, that is every value with a `Debug` representation.
+#[cfg(not(feature = "bound_values"))]
+pub trait BindValue {}
+
+#[cfg(not(feature = "bound_values"))]
+impl<T> BindValue for T {}
+
+impl Default for SqlBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'args> SqlBuilder<'args> {
+    pub fn new() -> Self {
+        SqlBuilder {
+            builder: QueryBuilder::new(""),
+            #[cfg(feature = "bound_values")]
+            bound_values: vec![],
+        }
+    }
+
+    pub fn push(&mut self, sql: impl Display) -> &mut Self {
+        self.builder.push(sql);
+        self
+    }
+
+    pub fn push_bind<T>(&mut self, value: T) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + BindValue,
+    {
+        #[cfg(feature = "bound_values")]
+        self.bound_values.push(format!("{value:?}"));
+        self.builder.push_bind(value);
+        self
+    }
+
+    pub fn build(&mut self) -> SqlQuery<'_> {
+        SqlQuery {
+            query: self.builder.build(),
+            #[cfg(feature = "bound_values")]
+            bound_values: std::mem::take(&mut self.bound_values),
+        }
+    }
+}
+
+/// A query built by [`sql!`](crate::sql)
+pub struct SqlQuery<'q> {
+    query: Query<'q, Postgres, PgArguments>,
+    #[cfg(feature = "bound_values")]
+    bound_values: Vec<String>,
+}
+
+impl<'q> Execute<'q, Postgres> for SqlQuery<'q> {
+    fn sql(&self) -> &'q str {
+        Execute::sql(&self.query)
+    }
+
+    fn statement(&self) -> Option<&PgStatement<'q>> {
+        Execute::statement(&self.query)
+    }
+
+    fn take_arguments(&mut self) -> Result<Option<PgArguments>, BoxDynError> {
+        Execute::take_arguments(&mut self.query)
+    }
+
+    fn persistent(&self) -> bool {
+        Execute::persistent(&self.query)
+    }
+}
+
+/// A query that can be run through the database access of the application: one built
+/// with [`sql!`](crate::sql), or a plain sqlx query
+pub trait SqlExecute<'q>: Execute<'q, Postgres> {
+    /// The `Debug` representation of each bound value, in order. Empty for sqlx
+    /// queries, whose values are only kept encoded.
+    #[cfg(feature = "bound_values")]
+    fn bound_values(&self) -> &[String] {
+        &[]
+    }
+}
+
+impl<'q> SqlExecute<'q> for SqlQuery<'q> {
+    #[cfg(feature = "bound_values")]
+    fn bound_values(&self) -> &[String] {
+        &self.bound_values
+    }
+}
+
+impl<'q, A: Send + IntoArguments<'q, Postgres>> SqlExecute<'q> for Query<'q, Postgres, A> {}
@@ -0,0 +1,30 @@
+use proptest::prelude::*;
+use sql::encode_sql_identifier;
+
+/// Reads a quoted identifier from the start of the input the way Postgres does: the
+/// identifier is delimited by double quotes and a double quote inside it is doubled.
+/// Returns the identifier and the rest of the input.
+fn parse_quoted_identifier(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.strip_prefix('"')?.char_indices().peekable();
+    let mut identifier = String::new();
+    while let Some((i, c)) = chars.next() {
+        if c == '"' && chars.next_if(|(_, c)| *c == '"').is_none() {
+            return Some((identifier, &input[i + 2..]));
+        }
+        identifier.push(c);
+    }
+    None
+}
+
+proptest! {
+    // A double quote right after the closing quote would continue the identifier, so
+    // the rest of the statement never starts with one
+    #[test]
+    fn test_identifiers_round_trip(identifier in any::<String>(), rest in "(?s)([^\"].*)?") {
+        let sql = format!("{}{rest}", encode_sql_identifier(&identifier));
+        prop_assert_eq!(
+            parse_quoted_identifier(&sql),
+            Some((identifier, rest.as_str()))
+        );
+    }
+}
@@ -0,0 +1,6 @@
+use sql::sql;
+
+fn main() {
+    let table = "things";
+    let _query = sql!("SELECT * FROM ${table:ident}");
+}
@@ -0,0 +1,5 @@
+use sql::sql;
+
+fn main() {
+    let _query = sql!("SELECT * FROM things WHERE id = ${thing_id}");
+}
@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+const SUB_DELIMS: &[u8] = b"!$&'()*+,;=";
+
+/// Characters that separate parameters and their keys and values in a query
+const QUERY_DELIMS: &[u8] = b"&=+;";
+
+/// Encodes everything but the unreserved characters, so that the result is safe to use
+/// in any part of a URI
+pub fn encode_component(input: &str) -> Cow<'_, str> {
+    encode_with(input, is_unreserved)
+}
+
+/// Encodes a single path segment, including any `/` in it
+pub fn encode_path_segment(input: &str) -> Cow<'_, str> {
+    encode_with(input, is_pchar)
+}
+
+/// Encodes each segment of a path, keeping the `/` between them
+pub fn encode_path(input: &str) -> Cow<'_, str> {
+    encode_with(input, |b| is_pchar(b) || b == b'/')
+}
+
+/// Encodes a key or a value of a query parameter. Besides the characters that are not
+/// allowed in a query, this encodes the ones that delimit parameters (`&`, `=`, `+`, `;`).
+pub fn encode_query_value(input: &str) -> Cow<'_, str> {
+    encode_with(input, |b| is_query_char(b) && !QUERY_DELIMS.contains(&b))
+}
+
+/// Encodes a fragment, which may contain `/` and `?` but not `#`
+pub fn encode_fragment(input: &str) -> Cow<'_, str> {
+    encode_with(input, is_query_char)
+}
+
+/// Decodes percent-encoded characters, or returns `None` if the encoding is invalid or
+/// the decoded bytes are not UTF-8
+pub fn percent_decode(input: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = tail.get(..2)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Unreserved characters of RFC 3986
+pub(crate) fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~".contains(&b)
+}
+
+/// Reserved characters of RFC 3986, the delimiters of the URI and its parts
+pub(crate) fn is_reserved(b: u8) -> bool {
+    b":/?#[]@".contains(&b) || SUB_DELIMS.contains(&b)
+}
+
+/// Characters allowed in a path segment by RFC 3986
+fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || SUB_DELIMS.contains(&b) || b == b':' || b == b'@'
+}
+
+/// Characters allowed in a query or fragment by RFC 3986
+fn is_query_char(b: u8) -> bool {
+    is_pchar(b) || b == b'/' || b == b'?'
+}
+
+fn encode_with(input: &str, allowed: impl Fn(u8) -> bool) -> Cow<'_, str> {
+    if input.bytes().all(&allowed) {
+        return Cow::Borrowed(input);
+    }
+    let mut encoded = String::with_capacity(input.len() * 3);
+    for b in input.bytes() {
+        if allowed(b) {
+            encoded.push(b as char);
+        } else {
+            write!(encoded, "%{b:02X}").unwrap();
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_component() {
+        assert_eq!(encode_component("a-b_c.d~e"), "a-b_c.d~e");
+        assert_eq!(encode_component("a/b?c#d e&f"), "a%2Fb%3Fc%23d%20e%26f");
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("a/b c/d:e@f"), "a/b%20c/d:e@f");
+        assert_eq!(encode_path("100%/ä?#"), "100%25/%C3%A4%3F%23");
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("a/b c;d=e"), "a%2Fb%20c;d=e");
+    }
+
+    #[test]
+    fn test_encode_query_value() {
+        assert_eq!(encode_query_value("a/b?c:d"), "a/b?c:d");
+        assert_eq!(
+            encode_query_value("a&b=c+d;e f#"),
+            "a%26b%3Dc%2Bd%3Be%20f%23"
+        );
+    }
+
+    #[test]
+    fn test_encode_fragment() {
+        assert_eq!(encode_fragment("section/1?a=b&c"), "section/1?a=b&c");
+        assert_eq!(encode_fragment("a#b c"), "a%23b%20c");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%20b%2Fc").as_deref(), Some("a b/c"));
+        assert_eq!(percent_decode("%C3%A4").as_deref(), Some("ä"));
+        assert_eq!(percent_decode("100%"), None);
+        assert_eq!(percent_decode("%+1"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+
+    #[test]
+    fn test_nothing_to_encode_is_borrowed() {
+        assert!(matches!(encode_path("a/b"), Cow::Borrowed("a/b")));
+    }
+}
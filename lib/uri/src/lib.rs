@@ -0,0 +1,16 @@
+extern crate macros;
+
+mod encode;
+mod matching;
+mod parse;
+mod query;
+mod template;
+mod value;
+
+pub use encode::*;
+pub use macros::*;
+pub use matching::*;
+pub use parse::*;
+pub use query::*;
+pub use template::*;
+pub use value::*;
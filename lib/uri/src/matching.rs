@@ -0,0 +1,284 @@
+use crate::percent_decode;
+
+/// A part of a template matched by [`match_template`]
+#[derive(Debug, Clone, Copy)]
+pub enum TemplatePart {
+    Literal(&'static str),
+    Variable(Capture),
+}
+
+/// How a variable is matched and decoded, by its format in the template
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Capture {
+    /// A plain variable, which can't contain `/`, `?`, `#` or `&`
+    Component,
+    /// A `:raw` variable, which can contain anything and is not decoded
+    Raw,
+    /// A `:path` variable, which can contain `/` but not `?` or `#`
+    Path,
+    /// A `:segment` variable, which can't contain `/`, `?` or `#`
+    Segment,
+    /// A `:query_value` variable, which can't contain `&` or `#`. A `+` is decoded as a
+    /// space, like in form-encoded queries.
+    QueryValue,
+    /// A `:fragment` variable, which can contain anything
+    Fragment,
+}
+
+impl Capture {
+    fn ends_at(self, c: char) -> bool {
+        match self {
+            Capture::Component => matches!(c, '/' | '?' | '#' | '&'),
+            Capture::Path => matches!(c, '?' | '#'),
+            Capture::Segment => matches!(c, '/' | '?' | '#'),
+            Capture::QueryValue => matches!(c, '&' | '#'),
+            Capture::Raw | Capture::Fragment => false,
+        }
+    }
+
+    fn decode(self, value: &str) -> Option<String> {
+        match self {
+            Capture::Raw => Some(value.to_string()),
+            Capture::QueryValue => percent_decode(&value.replace('+', " ")),
+            _ => percent_decode(value),
+        }
+    }
+}
+
+/// Matches the whole input against the template and returns the decoded values of its
+/// `N` variables, or `None` if the input doesn't match or a value can't be decoded.
+/// This is what `match_uri!` expands to.
+///
+/// Each variable takes the shortest value with which the rest of the template matches.
+pub fn match_template<const N: usize>(
+    template: &[TemplatePart],
+    input: &str,
+) -> Option<[String; N]> {
+    let mut values = Vec::with_capacity(N);
+    if !match_parts(template, input, &mut values) {
+        return None;
+    }
+    let decoded = values
+        .into_iter()
+        .map(|(capture, value)| capture.decode(value))
+        .collect::<Option<Vec<_>>>()?;
+    decoded.try_into().ok()
+}
+
+fn match_parts<'a>(
+    template: &[TemplatePart],
+    input: &'a str,
+    values: &mut Vec<(Capture, &'a str)>,
+) -> bool {
+    let mut matcher = Matcher {
+        template,
+        input,
+        stops: vec![Vec::new(); template.len()],
+        tried: vec![Vec::new(); template.len()],
+    };
+    matcher.match_from(0, 0, values)
+}
+
+/// Matches the parts of a template from left to right. A variable tries to end where the
+/// literal after it starts, shortest value first. Whether the rest of the template
+/// matches from an end doesn't depend on where the value started, so a variable tries
+/// each end at most once, which keeps matching linear in the length of the input.
+struct Matcher<'t, 'a> {
+    template: &'t [TemplatePart],
+    input: &'a str,
+    /// For each variable, the first character from each position of the input that ends
+    /// it. Built when the variable is first matched.
+    stops: Vec<Vec<usize>>,
+    /// For each variable, the next end from each position of the input that it hasn't
+    /// tried yet, as a disjoint-set forest. Built when the variable is first matched.
+    tried: Vec<Vec<usize>>,
+}
+
+impl<'a> Matcher<'_, 'a> {
+    fn match_from(
+        &mut self,
+        part: usize,
+        start: usize,
+        values: &mut Vec<(Capture, &'a str)>,
+    ) -> bool {
+        let input = self.input;
+        let capture = match self.template.get(part) {
+            None => return start == input.len(),
+            Some(TemplatePart::Literal(literal)) => {
+                return input[start..].starts_with(literal)
+                    && self.match_from(part + 1, start + literal.len(), values);
+            }
+            Some(TemplatePart::Variable(capture)) => *capture,
+        };
+        // The value can't go past the first character that ends the variable
+        let stop = self.stop(part, capture, start);
+        let next = match self.template.get(part + 1) {
+            Some(TemplatePart::Literal(literal)) if !literal.is_empty() => Some(*literal),
+            _ => None,
+        };
+        let mut end = match next {
+            // The literal can only start where the value stops
+            Some(literal) if literal.starts_with(|c| capture.ends_at(c)) => stop,
+            _ => start,
+        };
+        loop {
+            end = self.next_untried(part, end);
+            if end > stop {
+                return false;
+            }
+            self.tried[part][end] = end + 1;
+            if input.is_char_boundary(end)
+                && next.is_none_or(|literal| input[end..].starts_with(literal))
+            {
+                values.push((capture, &input[start..end]));
+                if self.match_from(part + 1, end, values) {
+                    return true;
+                }
+                values.pop();
+            }
+            end += 1;
+        }
+    }
+
+    /// The position of the first character from `start` on that ends the variable at
+    /// `part`, or the end of the input
+    fn stop(&mut self, part: usize, capture: Capture, start: usize) -> usize {
+        let stops = &mut self.stops[part];
+        if stops.is_empty() {
+            *stops = vec![self.input.len(); self.input.len() + 1];
+            for (i, c) in self.input.char_indices().rev() {
+                stops[i] = if capture.ends_at(c) {
+                    i
+                } else {
+                    stops[i + c.len_utf8()]
+                };
+            }
+        }
+        stops[start]
+    }
+
+    /// The first end from `end` on that the variable at `part` hasn't tried yet
+    fn next_untried(&mut self, part: usize, end: usize) -> usize {
+        let tried = &mut self.tried[part];
+        if tried.is_empty() {
+            *tried = (0..=self.input.len() + 1).collect();
+        }
+        let mut root = end;
+        while tried[root] != root {
+            root = tried[root];
+        }
+        let mut position = end;
+        while tried[position] != root {
+            position = std::mem::replace(&mut tried[position], root);
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{match_template, Capture, TemplatePart};
+
+    use Capture::*;
+    use TemplatePart::*;
+
+    #[test]
+    fn test_match() {
+        let template = [
+            Literal("http://localhost/"),
+            Variable(Component),
+            Literal("/x?p="),
+            Variable(QueryValue),
+        ];
+        assert_eq!(
+            match_template(&template, "http://localhost/a%20b/x?p=c+d%26"),
+            Some(["a b".to_string(), "c d&".to_string()])
+        );
+        assert_eq!(
+            match_template::<2>(&template, "http://localhost/a/b/x?p=c"),
+            None
+        );
+        assert_eq!(
+            match_template::<2>(&template, "http://localhost/a/x?p=c&q=d"),
+            None
+        );
+        assert_eq!(match_template::<2>(&template, "http://other/a/x?p=c"), None);
+    }
+
+    #[test]
+    fn test_match_path() {
+        let template = [Literal("/files/"), Variable(Path), Literal("/raw")];
+        assert_eq!(
+            match_template(&template, "/files/a/b/c/raw"),
+            Some(["a/b/c".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_raw_values_are_not_decoded() {
+        let template = [Variable(Raw), Literal("/callback#"), Variable(Fragment)];
+        assert_eq!(
+            match_template(&template, "http://a%20b/callback#x%2Fy"),
+            Some(["http://a%20b".to_string(), "x/y".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_invalid_encoding_does_not_match() {
+        let template = [Literal("/"), Variable(Segment)];
+        assert_eq!(match_template::<1>(&template, "/%ff"), None);
+        assert_eq!(match_template::<1>(&template, "/%zz"), None);
+    }
+
+    #[test]
+    fn test_shortest_values() {
+        let template = [Variable(Raw), Literal("aa"), Variable(Raw)];
+        assert_eq!(
+            match_template(&template, "xaaay"),
+            Some(["x".to_string(), "ay".to_string()])
+        );
+        let template = [
+            Variable(Component),
+            Literal("-"),
+            Variable(Segment),
+            Literal("/end"),
+        ];
+        assert_eq!(
+            match_template(&template, "a-b-c/end"),
+            Some(["a".to_string(), "b-c".to_string()])
+        );
+        let template = [Variable(Segment), Variable(Path)];
+        assert_eq!(
+            match_template(&template, "a/b"),
+            Some(["".to_string(), "a/b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_adversarial_input_is_matched_quickly() {
+        // Each variable can contain the literal after it, so that a backtracking matcher
+        // would try every way of splitting the input before failing on the last literal
+        let mut template = vec![];
+        for _ in 0..10 {
+            template.extend([Variable(Raw), Literal("a")]);
+        }
+        template.push(Literal("b"));
+        let input = "a".repeat(20_000);
+        let start = Instant::now();
+        assert_eq!(match_template::<10>(&template, &input), None);
+        let mut template = vec![Literal("/")];
+        for _ in 0..10 {
+            template.extend([Variable(QueryValue), Literal("-")]);
+        }
+        template.push(Variable(Segment));
+        let input = format!("/{}/", "-".repeat(20_000));
+        assert_eq!(match_template::<11>(&template, &input), None);
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use http::uri::InvalidUri;
+use http::Uri;
+
+/// Parses the URI built by `uri!`
+pub fn parse_uri(uri: String) -> Result<Uri, InvalidUri> {
+    Uri::try_from(uri)
+}
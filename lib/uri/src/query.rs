@@ -0,0 +1,662 @@
+use std::fmt::{Display, Formatter};
+
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple,
+    SerializeTupleStruct, Serializer,
+};
+
+use crate::encode_query_value;
+
+/// Appends the parameters to the URI as a query string, which is what the `:query`
+/// format of `try_format_uri!` expands to.
+///
+/// The parameters can be a struct, a map or a sequence of key-value pairs. `None`
+/// values are skipped and sequences repeat the key for each of their values. The
+/// query starts with `?`, or continues with `&` if the URI already has one, and
+/// nothing is appended when there are no values.
+///
+/// Fails if the parameters can't be represented as a query string, for example when a
+/// value is a nested struct. The URI is left unchanged then.
+pub fn push_query<T: Serialize + ?Sized>(uri: &mut String, params: &T) -> Result<(), QueryError> {
+    let len = uri.len();
+    let mut writer = QueryWriter::new(uri);
+    let result = params.serialize(ParamsSerializer(&mut writer));
+    if result.is_err() {
+        uri.truncate(len);
+    }
+    result
+}
+
+struct QueryWriter<'a> {
+    uri: &'a mut String,
+    separator: &'static str,
+}
+
+impl<'a> QueryWriter<'a> {
+    fn new(uri: &'a mut String) -> Self {
+        let separator = if !uri.contains('?') {
+            "?"
+        } else if uri.ends_with(['?', '&']) {
+            ""
+        } else {
+            "&"
+        };
+        QueryWriter { uri, separator }
+    }
+
+    fn push(&mut self, key: &str, values: &[String]) {
+        for value in values {
+            self.uri.push_str(self.separator);
+            self.uri.push_str(&encode_query_value(key));
+            self.uri.push('=');
+            self.uri.push_str(&encode_query_value(value));
+            self.separator = "&";
+        }
+    }
+}
+
+/// The parameters of [`push_query`] can't be represented as a query string
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QueryError(String);
+
+impl QueryError {
+    fn new(message: &str) -> Self {
+        QueryError(message.to_string())
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid query parameters: {}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl ser::Error for QueryError {
+    fn custom<T: Display>(msg: T) -> Self {
+        QueryError(msg.to_string())
+    }
+}
+
+/// Scalar methods of a serializer, all forwarded to its `scalar` method
+macro_rules! serialize_scalars {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                self.scalar(v)
+            }
+        )*
+    };
+}
+
+/// Serializes the parameters as a whole
+struct ParamsSerializer<'a, 'b>(&'a mut QueryWriter<'b>);
+
+impl ParamsSerializer<'_, '_> {
+    fn scalar(self, _value: impl Display) -> Result<(), QueryError> {
+        Err(QueryError::new(
+            "expected a struct, a map or a sequence of key-value pairs",
+        ))
+    }
+}
+
+impl<'a, 'b> Serializer for ParamsSerializer<'a, 'b> {
+    type Ok = ();
+    type Error = QueryError;
+    type SerializeSeq = PairsSerializer<'a, 'b>;
+    type SerializeTuple = PairsSerializer<'a, 'b>;
+    type SerializeTupleStruct = Impossible<(), QueryError>;
+    type SerializeTupleVariant = Impossible<(), QueryError>;
+    type SerializeMap = MapSerializer<'a, 'b>;
+    type SerializeStruct = MapSerializer<'a, 'b>;
+    type SerializeStructVariant = Impossible<(), QueryError>;
+
+    serialize_scalars!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    );
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), QueryError> {
+        self.scalar("bytes")
+    }
+
+    fn serialize_none(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), QueryError> {
+        self.scalar(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<(), QueryError> {
+        self.scalar(variant)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, QueryError> {
+        Ok(PairsSerializer(self.0))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, QueryError> {
+        Ok(PairsSerializer(self.0))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, QueryError> {
+        Err(QueryError::new("tuple structs are not supported"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QueryError> {
+        Err(QueryError::new("enums with fields are not supported"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, QueryError> {
+        Ok(MapSerializer {
+            writer: self.0,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, QueryError> {
+        Ok(MapSerializer {
+            writer: self.0,
+            key: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QueryError> {
+        Err(QueryError::new("enums with fields are not supported"))
+    }
+}
+
+struct PairsSerializer<'a, 'b>(&'a mut QueryWriter<'b>);
+
+impl SerializeSeq for PairsSerializer<'_, '_> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, pair: &T) -> Result<(), QueryError> {
+        let values = pair.serialize(ValueSerializer(Position::Pair))?;
+        if let Some((key, values)) = values.split_first() {
+            self.0.push(key, values);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+impl SerializeTuple for PairsSerializer<'_, '_> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, pair: &T) -> Result<(), QueryError> {
+        SerializeSeq::serialize_element(self, pair)
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+struct MapSerializer<'a, 'b> {
+    writer: &'a mut QueryWriter<'b>,
+    key: Option<String>,
+}
+
+impl SerializeMap for MapSerializer<'_, '_> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), QueryError> {
+        let mut keys = key.serialize(ValueSerializer(Position::Item))?;
+        if keys.len() != 1 {
+            return Err(QueryError::new("keys must be single values"));
+        }
+        self.key = keys.pop();
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QueryError> {
+        let key = self.key.take().unwrap_or_default();
+        let values = value.serialize(ValueSerializer(Position::Value))?;
+        self.writer.push(&key, &values);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+impl SerializeStruct for MapSerializer<'_, '_> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), QueryError> {
+        let values = value.serialize(ValueSerializer(Position::Value))?;
+        self.writer.push(key, &values);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+/// Where a value is in the parameters, which decides what it may contain
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Position {
+    /// The value of a parameter, a single value or a sequence of them
+    Value,
+    /// An item of a sequence, or a key
+    Item,
+    /// A key-value pair
+    Pair,
+}
+
+/// Serializes a value into the strings to set for its key. A key-value pair
+/// serializes into the key followed by its values.
+struct ValueSerializer(Position);
+
+impl ValueSerializer {
+    fn scalar(self, value: impl Display) -> Result<Vec<String>, QueryError> {
+        match self.0 {
+            Position::Pair => Err(QueryError::new("expected a key-value pair")),
+            _ => Ok(vec![value.to_string()]),
+        }
+    }
+
+    fn sequence(self) -> Result<SequenceSerializer, QueryError> {
+        match self.0 {
+            Position::Item => Err(QueryError::new("nested sequences are not supported")),
+            position => Ok(SequenceSerializer {
+                position,
+                values: vec![],
+                len: 0,
+            }),
+        }
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Vec<String>;
+    type Error = QueryError;
+    type SerializeSeq = SequenceSerializer;
+    type SerializeTuple = SequenceSerializer;
+    type SerializeTupleStruct = SequenceSerializer;
+    type SerializeTupleVariant = Impossible<Vec<String>, QueryError>;
+    type SerializeMap = Impossible<Vec<String>, QueryError>;
+    type SerializeStruct = Impossible<Vec<String>, QueryError>;
+    type SerializeStructVariant = Impossible<Vec<String>, QueryError>;
+
+    serialize_scalars!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    );
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Vec<String>, QueryError> {
+        Err(QueryError::new("bytes are not supported"))
+    }
+
+    fn serialize_none(self) -> Result<Vec<String>, QueryError> {
+        Ok(vec![])
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<String>, QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Vec<String>, QueryError> {
+        Ok(vec![])
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<String>, QueryError> {
+        Ok(vec![])
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Vec<String>, QueryError> {
+        self.scalar(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Vec<String>, QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Vec<String>, QueryError> {
+        Err(QueryError::new("enums with fields are not supported"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, QueryError> {
+        self.sequence()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, QueryError> {
+        self.sequence()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, QueryError> {
+        self.sequence()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QueryError> {
+        Err(QueryError::new("enums with fields are not supported"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, QueryError> {
+        Err(QueryError::new("nested maps are not supported"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, QueryError> {
+        Err(QueryError::new("nested structs are not supported"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QueryError> {
+        Err(QueryError::new("enums with fields are not supported"))
+    }
+}
+
+struct SequenceSerializer {
+    position: Position,
+    values: Vec<String>,
+    len: usize,
+}
+
+impl SerializeSeq for SequenceSerializer {
+    type Ok = Vec<String>;
+    type Error = QueryError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QueryError> {
+        let values = match (self.position, self.len) {
+            (Position::Pair, 0) => {
+                let key = value.serialize(ValueSerializer(Position::Item))?;
+                if key.len() != 1 {
+                    return Err(QueryError::new("keys must be single values"));
+                }
+                key
+            }
+            (Position::Pair, 1) => value.serialize(ValueSerializer(Position::Value))?,
+            (Position::Pair, _) => return Err(QueryError::new("expected a key-value pair")),
+            _ => value.serialize(ValueSerializer(Position::Item))?,
+        };
+        self.values.extend(values);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Vec<String>, QueryError> {
+        if self.position == Position::Pair && self.len != 2 {
+            return Err(QueryError::new("expected a key-value pair"));
+        }
+        Ok(self.values)
+    }
+}
+
+impl SerializeTuple for SequenceSerializer {
+    type Ok = Vec<String>;
+    type Error = QueryError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QueryError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Vec<String>, QueryError> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for SequenceSerializer {
+    type Ok = Vec<String>;
+    type Error = QueryError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QueryError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Vec<String>, QueryError> {
+        SerializeSeq::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::push_query;
+
+    fn query(uri: &str, params: &(impl Serialize + ?Sized)) -> String {
+        let mut uri = uri.to_string();
+        push_query(&mut uri, params).unwrap();
+        uri
+    }
+
+    /// The error message, after checking that the URI was left unchanged
+    fn query_error(uri: &str, params: &(impl Serialize + ?Sized)) -> String {
+        let mut result = uri.to_string();
+        let error = push_query(&mut result, params).unwrap_err();
+        assert_eq!(result, uri);
+        error.to_string()
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Order {
+        NewestFirst,
+    }
+
+    #[derive(Serialize)]
+    struct Search {
+        name: &'static str,
+        limit: Option<u32>,
+        tags: Vec<&'static str>,
+        order: Order,
+    }
+
+    #[test]
+    fn test_struct() {
+        let search = Search {
+            name: "a thing&more",
+            limit: None,
+            tags: vec!["red", "blue"],
+            order: Order::NewestFirst,
+        };
+        assert_eq!(
+            query("/things", &search),
+            "/things?name=a%20thing%26more&tags=red&tags=blue&order=newest_first"
+        );
+    }
+
+    #[test]
+    fn test_pairs() {
+        assert_eq!(
+            query("/things", &[("limit", Some(10)), ("offset", None)]),
+            "/things?limit=10"
+        );
+        assert_eq!(
+            query("/things", &vec![("id", vec![1, 2])]),
+            "/things?id=1&id=2"
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        let map = BTreeMap::from([("b", "2"), ("a", "1")]);
+        assert_eq!(query("/things", &map), "/things?a=1&b=2");
+    }
+
+    #[test]
+    fn test_nothing_emitted() {
+        let pairs: [(&str, Option<&str>); 1] = [("name", None)];
+        assert_eq!(query("/things", &pairs), "/things");
+        assert_eq!(query("/things", &None::<Search>), "/things");
+    }
+
+    #[test]
+    fn test_existing_query() {
+        assert_eq!(query("/things?a=1", &[("b", 2)]), "/things?a=1&b=2");
+        assert_eq!(query("/things?", &[("b", 2)]), "/things?b=2");
+    }
+
+    #[test]
+    fn test_nested_struct() {
+        #[derive(Serialize)]
+        struct Outer {
+            page: u32,
+            search: Search,
+        }
+        assert_eq!(
+            query_error(
+                "/things?a=1",
+                &Outer {
+                    page: 2,
+                    search: Search {
+                        name: "a",
+                        limit: None,
+                        tags: vec![],
+                        order: Order::NewestFirst,
+                    },
+                },
+            ),
+            "Invalid query parameters: nested structs are not supported"
+        );
+    }
+
+    #[test]
+    fn test_enum_with_fields() {
+        #[derive(Serialize)]
+        enum Filter {
+            Named(&'static str),
+        }
+        assert_eq!(
+            query_error("/things", &[("filter", Filter::Named("a"))]),
+            "Invalid query parameters: enums with fields are not supported"
+        );
+    }
+
+    #[test]
+    fn test_sequence_of_values() {
+        assert_eq!(
+            query_error("/things", &["a", "b"]),
+            "Invalid query parameters: expected a key-value pair"
+        );
+    }
+}
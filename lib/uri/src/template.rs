@@ -0,0 +1,770 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::encode::{is_reserved, is_unreserved};
+
+/// A URI template of RFC 6570 (levels 1 to 4) that is parsed and expanded at runtime, e.g.
+/// one read from the configuration. Templates known at compile time are better written
+/// with `format_uri!`, which checks them while compiling.
+///
+/// ```
+/// use uri::{TemplateValue, TemplateVars, UriTemplate};
+///
+/// let template = UriTemplate::parse("http://localhost/things{/id}{?fields*}").unwrap();
+/// let vars = TemplateVars::new()
+///     .set("id", "a b")
+///     .set("fields", TemplateValue::list(["name", "size"]));
+/// assert_eq!(
+///     template.expand(&vars),
+///     "http://localhost/things/a%20b?fields=name&fields=size"
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Part {
+    /// Literal text, already encoded where needed
+    Literal(String),
+    Expression {
+        operator: Operator,
+        variables: Vec<VarSpec>,
+    },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct VarSpec {
+    name: String,
+    modifier: Modifier,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Modifier {
+    None,
+    /// `{var:3}`, the first characters of a string
+    Prefix(usize),
+    /// `{var*}`, each item of a list or map as a separate value
+    Explode,
+}
+
+/// The operator of an expression, see the table in appendix A of RFC 6570
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    Path,
+    PathParameter,
+    Query,
+    QueryContinuation,
+}
+
+impl Operator {
+    fn parse(c: char) -> Option<Operator> {
+        match c {
+            '+' => Some(Operator::Reserved),
+            '#' => Some(Operator::Fragment),
+            '.' => Some(Operator::Label),
+            '/' => Some(Operator::Path),
+            ';' => Some(Operator::PathParameter),
+            '?' => Some(Operator::Query),
+            '&' => Some(Operator::QueryContinuation),
+            _ => None,
+        }
+    }
+
+    /// Added before the first defined variable
+    fn first(self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved => "",
+            Operator::Fragment => "#",
+            Operator::Label => ".",
+            Operator::Path => "/",
+            Operator::PathParameter => ";",
+            Operator::Query => "?",
+            Operator::QueryContinuation => "&",
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved | Operator::Fragment => ",",
+            Operator::Label => ".",
+            Operator::Path => "/",
+            Operator::PathParameter => ";",
+            Operator::Query | Operator::QueryContinuation => "&",
+        }
+    }
+
+    /// Whether values are preceded by the name of the variable
+    fn named(self) -> bool {
+        matches!(
+            self,
+            Operator::PathParameter | Operator::Query | Operator::QueryContinuation
+        )
+    }
+
+    /// Added after the name instead of `=` when the value is empty
+    fn if_empty(self) -> &'static str {
+        match self {
+            Operator::Query | Operator::QueryContinuation => "=",
+            _ => "",
+        }
+    }
+
+    /// Whether reserved characters and percent-encoded triplets are kept
+    fn allow_reserved(self) -> bool {
+        matches!(self, Operator::Reserved | Operator::Fragment)
+    }
+}
+
+/// The value of a variable of a [`UriTemplate`]. Empty lists and maps are undefined, like
+/// variables without a value, and are left out of the expansion.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TemplateValue {
+    String(String),
+    List(Vec<String>),
+    /// Key-value pairs, expanded in this order
+    Map(Vec<(String, String)>),
+}
+
+impl TemplateValue {
+    pub fn list<T: Into<String>>(items: impl IntoIterator<Item = T>) -> Self {
+        TemplateValue::List(items.into_iter().map(Into::into).collect())
+    }
+
+    pub fn map<K: Into<String>, V: Into<String>>(pairs: impl IntoIterator<Item = (K, V)>) -> Self {
+        TemplateValue::Map(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    fn is_defined(&self) -> bool {
+        match self {
+            TemplateValue::String(_) => true,
+            TemplateValue::List(items) => !items.is_empty(),
+            TemplateValue::Map(pairs) => !pairs.is_empty(),
+        }
+    }
+}
+
+impl From<&str> for TemplateValue {
+    fn from(value: &str) -> Self {
+        TemplateValue::String(value.to_string())
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(value: String) -> Self {
+        TemplateValue::String(value)
+    }
+}
+
+/// The values of the variables to expand a [`UriTemplate`] with
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TemplateVars(BTreeMap<String, TemplateValue>);
+
+impl TemplateVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<TemplateValue>) -> Self {
+        self.0.insert(name.into(), value.into());
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&TemplateValue> {
+        self.0.get(name).filter(|value| value.is_defined())
+    }
+}
+
+/// Why a URI template could not be parsed, with the byte offset of the problem
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UriTemplateError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl UriTemplateError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        UriTemplateError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for UriTemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid URI template at {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for UriTemplateError {}
+
+impl UriTemplate {
+    pub fn parse(template: &str) -> Result<UriTemplate, UriTemplateError> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut position = 0;
+        while let Some(c) = template[position..].chars().next() {
+            match c {
+                '{' => {
+                    let end = template[position..]
+                        .find('}')
+                        .map(|end| position + end)
+                        .ok_or_else(|| UriTemplateError::new(position, "unclosed expression"))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_expression(
+                        &template[position + 1..end],
+                        position + 1,
+                    )?);
+                    position = end + 1;
+                }
+                '%' => {
+                    let hex = template[position + 1..].get(..2).unwrap_or_default();
+                    if hex.len() != 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(UriTemplateError::new(position, "invalid percent-encoding"));
+                    }
+                    literal.push('%');
+                    literal.push_str(hex);
+                    position += 3;
+                }
+                c if c.is_control() || " \"'<>\\^`|}".contains(c) => {
+                    return Err(UriTemplateError::new(
+                        position,
+                        format!("character {c:?} is not allowed"),
+                    ));
+                }
+                c => {
+                    push_encoded(&mut literal, c.encode_utf8(&mut [0; 4]), true);
+                    position += c.len_utf8();
+                }
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(UriTemplate {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    /// Expands the template, leaving out the variables without a value
+    pub fn expand(&self, vars: &TemplateVars) -> String {
+        let mut uri = String::with_capacity(self.template.len() * 2);
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => uri.push_str(literal),
+                Part::Expression {
+                    operator,
+                    variables,
+                } => expand_expression(&mut uri, *operator, variables, vars),
+            }
+        }
+        uri
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+}
+
+/// Parses the inside of an expression, starting at the given offset of the template
+fn parse_expression(expression: &str, position: usize) -> Result<Part, UriTemplateError> {
+    let first = expression
+        .chars()
+        .next()
+        .ok_or_else(|| UriTemplateError::new(position, "empty expression"))?;
+    let (operator, list, mut position) = match Operator::parse(first) {
+        Some(operator) => (operator, &expression[1..], position + 1),
+        None if "=,!@|".contains(first) => {
+            return Err(UriTemplateError::new(
+                position,
+                format!("operator {first:?} is reserved"),
+            ));
+        }
+        None => (Operator::Simple, expression, position),
+    };
+    let mut variables = vec![];
+    for spec in list.split(',') {
+        variables.push(parse_var_spec(spec, position)?);
+        position += spec.len() + 1;
+    }
+    Ok(Part::Expression {
+        operator,
+        variables,
+    })
+}
+
+fn parse_var_spec(spec: &str, position: usize) -> Result<VarSpec, UriTemplateError> {
+    let (name, modifier) = if let Some(name) = spec.strip_suffix('*') {
+        (name, Modifier::Explode)
+    } else if let Some((name, length)) = spec.split_once(':') {
+        let valid = length.bytes().all(|b| b.is_ascii_digit()) && !length.starts_with('0');
+        match length.parse::<usize>() {
+            Ok(length @ 1..=9999) if valid => (name, Modifier::Prefix(length)),
+            _ => {
+                return Err(UriTemplateError::new(
+                    position + name.len() + 1,
+                    format!("invalid prefix length {length:?}, expected 1 to 9999"),
+                ));
+            }
+        }
+    } else {
+        (spec, Modifier::None)
+    };
+    if !is_var_name(name) {
+        return Err(UriTemplateError::new(
+            position,
+            format!("invalid variable name {name:?}"),
+        ));
+    }
+    Ok(VarSpec {
+        name: name.to_string(),
+        modifier,
+    })
+}
+
+/// Names are made of letters, digits, `_` and percent-encoded triplets, separated by dots
+fn is_var_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let bytes = part.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' if bytes.len() > i + 2
+                    && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit) =>
+                {
+                    i += 3
+                }
+                b if b.is_ascii_alphanumeric() || b == b'_' => i += 1,
+                _ => return false,
+            }
+        }
+        !part.is_empty()
+    })
+}
+
+fn expand_expression(
+    uri: &mut String,
+    operator: Operator,
+    variables: &[VarSpec],
+    vars: &TemplateVars,
+) {
+    let allow_reserved = operator.allow_reserved();
+    let mut first = true;
+    for var in variables {
+        let Some(value) = vars.get(&var.name) else {
+            continue;
+        };
+        uri.push_str(if first {
+            operator.first()
+        } else {
+            operator.separator()
+        });
+        first = false;
+        match (value, var.modifier) {
+            (TemplateValue::String(value), modifier) => {
+                let value = match modifier {
+                    Modifier::Prefix(length) => match value.char_indices().nth(length) {
+                        Some((end, _)) => &value[..end],
+                        None => value,
+                    },
+                    _ => value,
+                };
+                if operator.named() {
+                    push_name(uri, operator, &var.name, value.is_empty());
+                }
+                push_encoded(uri, value, allow_reserved);
+            }
+            (TemplateValue::List(items), Modifier::Explode) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        uri.push_str(operator.separator());
+                    }
+                    if operator.named() {
+                        push_name(uri, operator, &var.name, item.is_empty());
+                    }
+                    push_encoded(uri, item, allow_reserved);
+                }
+            }
+            (TemplateValue::Map(pairs), Modifier::Explode) => {
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        uri.push_str(operator.separator());
+                    }
+                    push_encoded(uri, key, allow_reserved);
+                    if operator.named() && value.is_empty() {
+                        uri.push_str(operator.if_empty());
+                    } else {
+                        uri.push('=');
+                    }
+                    push_encoded(uri, value, allow_reserved);
+                }
+            }
+            // Without the explode modifier, lists and maps are joined with commas
+            (TemplateValue::List(items), _) => {
+                if operator.named() {
+                    push_name(uri, operator, &var.name, false);
+                }
+                push_joined(uri, items.iter().map(String::as_str), allow_reserved);
+            }
+            (TemplateValue::Map(pairs), _) => {
+                if operator.named() {
+                    push_name(uri, operator, &var.name, false);
+                }
+                let items = pairs.iter().flat_map(|(k, v)| [k.as_str(), v.as_str()]);
+                push_joined(uri, items, allow_reserved);
+            }
+        }
+    }
+}
+
+fn push_name(uri: &mut String, operator: Operator, name: &str, empty: bool) {
+    uri.push_str(name);
+    if empty {
+        uri.push_str(operator.if_empty());
+    } else {
+        uri.push('=');
+    }
+}
+
+fn push_joined<'a>(uri: &mut String, items: impl Iterator<Item = &'a str>, allow_reserved: bool) {
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            uri.push(',');
+        }
+        push_encoded(uri, item, allow_reserved);
+    }
+}
+
+/// Encodes everything but the unreserved characters, or also keeps the reserved characters
+/// and percent-encoded triplets if `allow_reserved` is set
+fn push_encoded(uri: &mut String, value: &str, allow_reserved: bool) {
+    let bytes = value.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        let is_triplet = || {
+            b == b'%'
+                && bytes.len() > i + 2
+                && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit)
+        };
+        if is_unreserved(b) || allow_reserved && (is_reserved(b) || is_triplet()) {
+            uri.push(b as char);
+        } else {
+            write!(uri, "%{b:02X}").unwrap();
+        }
+    }
+}
+
+impl FromStr for UriTemplate {
+    type Err = UriTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UriTemplate::parse(s)
+    }
+}
+
+impl Display for UriTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl<'de> Deserialize<'de> for UriTemplate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let template = String::deserialize(deserializer)?;
+        UriTemplate::parse(&template).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::value::{Error, StrDeserializer};
+    use serde::de::IntoDeserializer;
+
+    use super::*;
+
+    /// The variables of the examples in section 3.2 of RFC 6570
+    fn rfc_vars() -> TemplateVars {
+        TemplateVars::new()
+            .set("count", TemplateValue::list(["one", "two", "three"]))
+            .set("dom", TemplateValue::list(["example", "com"]))
+            .set("dub", "me/too")
+            .set("hello", "Hello World!")
+            .set("half", "50%")
+            .set("var", "value")
+            .set("who", "fred")
+            .set("base", "http://example.com/home/")
+            .set("path", "/foo/bar")
+            .set("list", TemplateValue::list(["red", "green", "blue"]))
+            .set(
+                "keys",
+                TemplateValue::map([("semi", ";"), ("dot", "."), ("comma", ",")]),
+            )
+            .set("v", "6")
+            .set("x", "1024")
+            .set("y", "768")
+            .set("empty", "")
+            .set("empty_keys", TemplateValue::Map(vec![]))
+    }
+
+    fn assert_expansions(cases: &[(&str, &str)]) {
+        let vars = rfc_vars();
+        for (template, expected) in cases {
+            let expanded = UriTemplate::parse(template).unwrap().expand(&vars);
+            assert_eq!(&expanded, expected, "expanding {template}");
+        }
+    }
+
+    #[test]
+    fn test_simple_expansion() {
+        assert_expansions(&[
+            ("{var}", "value"),
+            ("{hello}", "Hello%20World%21"),
+            ("{half}", "50%25"),
+            ("O{empty}X", "OX"),
+            ("O{undef}X", "OX"),
+            ("{x,y}", "1024,768"),
+            ("{x,hello,y}", "1024,Hello%20World%21,768"),
+            ("?{x,empty}", "?1024,"),
+            ("?{x,undef}", "?1024"),
+            ("?{undef,y}", "?768"),
+            ("{var:3}", "val"),
+            ("{var:30}", "value"),
+            ("{list}", "red,green,blue"),
+            ("{list*}", "red,green,blue"),
+            ("{keys}", "semi,%3B,dot,.,comma,%2C"),
+            ("{keys*}", "semi=%3B,dot=.,comma=%2C"),
+        ]);
+    }
+
+    #[test]
+    fn test_reserved_expansion() {
+        assert_expansions(&[
+            ("{+var}", "value"),
+            ("{+hello}", "Hello%20World!"),
+            ("{+half}", "50%25"),
+            ("{base}index", "http%3A%2F%2Fexample.com%2Fhome%2Findex"),
+            ("{+base}index", "http://example.com/home/index"),
+            ("O{+empty}X", "OX"),
+            ("O{+undef}X", "OX"),
+            ("{+path}/here", "/foo/bar/here"),
+            ("here?ref={+path}", "here?ref=/foo/bar"),
+            ("up{+path}{var}/here", "up/foo/barvalue/here"),
+            ("{+x,hello,y}", "1024,Hello%20World!,768"),
+            ("{+path,x}/here", "/foo/bar,1024/here"),
+            ("{+path:6}/here", "/foo/b/here"),
+            ("{+list}", "red,green,blue"),
+            ("{+list*}", "red,green,blue"),
+            ("{+keys}", "semi,;,dot,.,comma,,"),
+            ("{+keys*}", "semi=;,dot=.,comma=,"),
+        ]);
+    }
+
+    #[test]
+    fn test_fragment_expansion() {
+        assert_expansions(&[
+            ("{#var}", "#value"),
+            ("{#hello}", "#Hello%20World!"),
+            ("{#half}", "#50%25"),
+            ("foo{#empty}", "foo#"),
+            ("foo{#undef}", "foo"),
+            ("{#x,hello,y}", "#1024,Hello%20World!,768"),
+            ("{#path,x}/here", "#/foo/bar,1024/here"),
+            ("{#path:6}/here", "#/foo/b/here"),
+            ("{#list}", "#red,green,blue"),
+            ("{#list*}", "#red,green,blue"),
+            ("{#keys}", "#semi,;,dot,.,comma,,"),
+            ("{#keys*}", "#semi=;,dot=.,comma=,"),
+        ]);
+    }
+
+    #[test]
+    fn test_label_expansion() {
+        assert_expansions(&[
+            ("{.who}", ".fred"),
+            ("{.who,who}", ".fred.fred"),
+            ("{.half,who}", ".50%25.fred"),
+            ("www{.dom*}", "www.example.com"),
+            ("X{.var}", "X.value"),
+            ("X{.empty}", "X."),
+            ("X{.undef}", "X"),
+            ("X{.var:3}", "X.val"),
+            ("X{.list}", "X.red,green,blue"),
+            ("X{.list*}", "X.red.green.blue"),
+            ("X{.keys}", "X.semi,%3B,dot,.,comma,%2C"),
+            ("X{.keys*}", "X.semi=%3B.dot=..comma=%2C"),
+            ("X{.empty_keys}", "X"),
+            ("X{.empty_keys*}", "X"),
+        ]);
+    }
+
+    #[test]
+    fn test_path_expansion() {
+        assert_expansions(&[
+            ("{/who}", "/fred"),
+            ("{/who,who}", "/fred/fred"),
+            ("{/half,who}", "/50%25/fred"),
+            ("{/who,dub}", "/fred/me%2Ftoo"),
+            ("{/var}", "/value"),
+            ("{/var,empty}", "/value/"),
+            ("{/var,undef}", "/value"),
+            ("{/var,x}/here", "/value/1024/here"),
+            ("{/var:1,var}", "/v/value"),
+            ("{/list}", "/red,green,blue"),
+            ("{/list*}", "/red/green/blue"),
+            ("{/list*,path:4}", "/red/green/blue/%2Ffoo"),
+            ("{/keys}", "/semi,%3B,dot,.,comma,%2C"),
+            ("{/keys*}", "/semi=%3B/dot=./comma=%2C"),
+        ]);
+    }
+
+    #[test]
+    fn test_path_parameter_expansion() {
+        assert_expansions(&[
+            ("{;who}", ";who=fred"),
+            ("{;half}", ";half=50%25"),
+            ("{;empty}", ";empty"),
+            ("{;v,empty,who}", ";v=6;empty;who=fred"),
+            ("{;v,bar,who}", ";v=6;who=fred"),
+            ("{;x,y}", ";x=1024;y=768"),
+            ("{;x,y,empty}", ";x=1024;y=768;empty"),
+            ("{;x,y,undef}", ";x=1024;y=768"),
+            ("{;hello:5}", ";hello=Hello"),
+            ("{;list}", ";list=red,green,blue"),
+            ("{;list*}", ";list=red;list=green;list=blue"),
+            ("{;keys}", ";keys=semi,%3B,dot,.,comma,%2C"),
+            ("{;keys*}", ";semi=%3B;dot=.;comma=%2C"),
+        ]);
+    }
+
+    #[test]
+    fn test_query_expansion() {
+        assert_expansions(&[
+            ("{?who}", "?who=fred"),
+            ("{?half}", "?half=50%25"),
+            ("{?x,y}", "?x=1024&y=768"),
+            ("{?x,y,empty}", "?x=1024&y=768&empty="),
+            ("{?x,y,undef}", "?x=1024&y=768"),
+            ("{?var:3}", "?var=val"),
+            ("{?list}", "?list=red,green,blue"),
+            ("{?list*}", "?list=red&list=green&list=blue"),
+            ("{?keys}", "?keys=semi,%3B,dot,.,comma,%2C"),
+            ("{?keys*}", "?semi=%3B&dot=.&comma=%2C"),
+        ]);
+    }
+
+    #[test]
+    fn test_query_continuation_expansion() {
+        assert_expansions(&[
+            ("{&who}", "&who=fred"),
+            ("{&half}", "&half=50%25"),
+            ("?fixed=yes{&x}", "?fixed=yes&x=1024"),
+            ("{&x,y,empty}", "&x=1024&y=768&empty="),
+            ("{&var:3}", "&var=val"),
+            ("{&list}", "&list=red,green,blue"),
+            ("{&list*}", "&list=red&list=green&list=blue"),
+            ("{&keys}", "&keys=semi,%3B,dot,.,comma,%2C"),
+            ("{&keys*}", "&semi=%3B&dot=.&comma=%2C"),
+        ]);
+    }
+
+    #[test]
+    fn test_prefix_counts_characters() {
+        let vars = TemplateVars::new().set("word", "äöü");
+        let template = UriTemplate::parse("{word:2}").unwrap();
+        assert_eq!(template.expand(&vars), "%C3%A4%C3%B6");
+    }
+
+    #[test]
+    fn test_literals_are_encoded() {
+        let template = UriTemplate::parse("/caf%C3%A9/ä?a=b").unwrap();
+        assert_eq!(
+            template.expand(&TemplateVars::new()),
+            "/caf%C3%A9/%C3%A4?a=b"
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        let error = |template: &str| UriTemplate::parse(template).unwrap_err().to_string();
+        assert_eq!(
+            error("/a/{b"),
+            "Invalid URI template at 3: unclosed expression"
+        );
+        assert_eq!(
+            error("/a/{}"),
+            "Invalid URI template at 4: empty expression"
+        );
+        assert_eq!(
+            error("/a}"),
+            "Invalid URI template at 2: character '}' is not allowed"
+        );
+        assert_eq!(
+            error("/a b"),
+            "Invalid URI template at 2: character ' ' is not allowed"
+        );
+        assert_eq!(
+            error("/100%"),
+            "Invalid URI template at 4: invalid percent-encoding"
+        );
+        assert_eq!(
+            error("{=a}"),
+            "Invalid URI template at 1: operator '=' is reserved"
+        );
+        assert_eq!(
+            error("{a,b-c}"),
+            "Invalid URI template at 3: invalid variable name \"b-c\""
+        );
+        assert_eq!(
+            error("{a..b}"),
+            "Invalid URI template at 1: invalid variable name \"a..b\""
+        );
+        assert_eq!(
+            error("{?a:0}"),
+            "Invalid URI template at 4: invalid prefix length \"0\", expected 1 to 9999"
+        );
+        assert_eq!(
+            error("{a:10000}"),
+            "Invalid URI template at 3: invalid prefix length \"10000\", expected 1 to 9999"
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let deserializer: StrDeserializer<Error> = "http://localhost{/id}".into_deserializer();
+        let template = UriTemplate::deserialize(deserializer).unwrap();
+        assert_eq!(template.as_str(), "http://localhost{/id}");
+        assert_eq!(
+            template.expand(&TemplateVars::new().set("id", "1")),
+            "http://localhost/1"
+        );
+
+        let deserializer: StrDeserializer<Error> = "http://localhost{/id".into_deserializer();
+        assert_eq!(
+            UriTemplate::deserialize(deserializer)
+                .unwrap_err()
+                .to_string(),
+            "Invalid URI template at 16: unclosed expression"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+
+/// A variable of `format_uri!`, which can be anything that is `AsRef<str>` or `Display`.
+/// Calling `uri_value` on a reference to it prefers `AsRef<str>`, so that strings are
+/// not copied.
+#[doc(hidden)]
+pub struct UriValue<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait AsRefUriValue {
+    fn uri_value(&self) -> Cow<'_, str>;
+}
+
+impl<T: AsRef<str> + ?Sized> AsRefUriValue for UriValue<'_, T> {
+    fn uri_value(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.0.as_ref())
+    }
+}
+
+#[doc(hidden)]
+pub trait DisplayUriValue {
+    fn uri_value(&self) -> Cow<'_, str>;
+}
+
+impl<T: Display + ?Sized> DisplayUriValue for &UriValue<'_, T> {
+    fn uri_value(&self) -> Cow<'_, str> {
+        Cow::Owned(self.0.to_string())
+    }
+}
@@ -0,0 +1,36 @@
+use proptest::prelude::*;
+use uri::{encode_fragment, encode_path, encode_path_segment, encode_query_value};
+
+fn decode(encoded: &str) -> String {
+    urlencoding::decode(encoded).unwrap().into_owned()
+}
+
+proptest! {
+    #[test]
+    fn test_paths_round_trip(path in any::<String>()) {
+        let encoded = encode_path(&path);
+        prop_assert_eq!(encoded.split('/').count(), path.split('/').count());
+        prop_assert_eq!(decode(&encoded), path);
+    }
+
+    #[test]
+    fn test_path_segments_round_trip(segment in any::<String>()) {
+        let encoded = encode_path_segment(&segment);
+        prop_assert!(!encoded.contains(['/', '?', '#']), "{encoded}");
+        prop_assert_eq!(decode(&encoded), segment);
+    }
+
+    #[test]
+    fn test_query_values_round_trip(value in any::<String>()) {
+        let encoded = encode_query_value(&value);
+        prop_assert!(!encoded.contains(['&', '=', '+', ';', '#']), "{encoded}");
+        prop_assert_eq!(decode(&encoded), value);
+    }
+
+    #[test]
+    fn test_fragments_round_trip(fragment in any::<String>()) {
+        let encoded = encode_fragment(&fragment);
+        prop_assert!(!encoded.contains('#'), "{encoded}");
+        prop_assert_eq!(decode(&encoded), fragment);
+    }
+}
@@ -1,11 +1,13 @@
 use proc_macro::TokenStream as TS;
 
-use crate::proc_format_uri::proc_format_uri;
+use crate::proc_format_uri::{proc_format_uri, proc_match_uri, proc_try_format_uri, proc_uri};
 
 mod proc_format_uri;
 
 /// This procedural macro allows you to construct URLs or URIs with all the variables
-/// automatically URL-encoded (using the urlencoding crate).
+/// automatically URL-encoded. Variables can be anything that implements `AsRef<str>` or
+/// `Display`, such as strings, numbers and UUIDs. The expanded code uses the `uri` crate,
+/// which also re-exports this macro.
 ///
 /// Usage:
 ///
@@ -26,7 +28,68 @@ mod proc_format_uri;
 ///     param=my.param
 /// );
 /// ```
+///
+/// To append a query string, use [`try_format_uri!`] with a `:query` variable.
+///
+/// Plain variables are encoded so that they are safe anywhere. The following formats
+/// only encode what the part of the URI they are in requires (RFC 3986), and using them
+/// anywhere else is a compile error:
+///
+/// - `{p:path}`: a path, encoding each segment but keeping `/`
+/// - `{s:segment}`: a single path segment
+/// - `{q:query_value}`: a key or value of a query parameter
+/// - `{f:fragment}`: a fragment
+///
+/// ```ignore
+/// let uri = format_uri!("http://localhost/files/{dir:path}?name={name:query_value}");
+/// ```
+///
+/// Use `{{` and `}}` for literal braces, e.g. `"/docs/{{name}}"` is `/docs/{name}`. For
+/// templates that are only known at runtime, see `uri::UriTemplate`.
 #[proc_macro]
 pub fn format_uri(input: TS) -> TS {
     proc_format_uri(input.into()).into()
 }
+
+/// Like [`format_uri!`], but also takes a `:query` variable, which appends a query string
+/// built from a `Serialize` struct, map or sequence of key-value pairs. `None` values are
+/// skipped, sequences repeat the key and the `?` is left out when there is nothing to
+/// append (see `uri::push_query`). Not every value can be a query string, so the macro
+/// always returns a `Result<String, uri::QueryError>`.
+///
+/// ```ignore
+/// let uri = try_format_uri!("http://localhost/things{params:query}")?;
+/// ```
+#[proc_macro]
+pub fn try_format_uri(input: TS) -> TS {
+    proc_try_format_uri(input.into()).into()
+}
+
+/// Like [`format_uri!`], but checks at compile time that the template has only characters
+/// allowed in a URI outside the variables, and returns the URI parsed as
+/// `Result<http::Uri, http::uri::InvalidUri>`.
+///
+/// ```ignore
+/// let uri: http::Uri = uri!("http://localhost/things/{id}")?;
+/// ```
+#[proc_macro]
+pub fn uri(input: TS) -> TS {
+    proc_uri(input.into()).into()
+}
+
+/// Matches a URI against a template with the same syntax as [`format_uri!`] and returns
+/// the percent-decoded values of its variables, or `None` if the URI doesn't match. A
+/// template with one variable returns its value as a `String`, and a template with more
+/// returns a tuple of them in the order of the template. `:raw` values are not decoded.
+///
+/// The URI can be anything that implements `AsRef<str>` or `Display`, such as an
+/// `http::Uri`. It must match the template as a whole, so for example query parameters
+/// must be in the order of the template.
+///
+/// ```ignore
+/// let (a, b) = match_uri!("http://host/{a}/x?p={b}", uri)?;
+/// ```
+#[proc_macro]
+pub fn match_uri(input: TS) -> TS {
+    proc_match_uri(input.into()).into()
+}
@@ -1,11 +1,11 @@
 use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use regex::Regex;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::spanned::Spanned;
 use syn::{parse2, Error, Expr, LitStr, Token};
 
 /// Variable assignment
@@ -21,7 +21,51 @@ struct UriTemplate {
     assignments: Vec<Assignment>,
 }
 
-const BINDING_RE: &str = "_?\\{([a-zA-Z_][a-zA-Z0-9_]*(:[a-z_]+)*)\\}";
+/// Matches the escaped braces `{{` and `}}`, or a variable
+const BINDING_RE: &str = "\\{\\{|\\}\\}|_?\\{([a-zA-Z_][a-zA-Z0-9_]*(:[a-z_]+)*)\\}";
+
+/// A piece of the template: literal text with the escaped braces replaced, or a variable
+enum Segment<'a> {
+    Literal(String),
+    Variable(&'a str),
+}
+
+/// Splits the template into literal text and variables
+fn split_template(uri: &str) -> Vec<Segment<'_>> {
+    let re = Regex::new(BINDING_RE).unwrap();
+    let mut segments = vec![];
+    let mut literal = String::new();
+    let mut offset = 0;
+    for cap in re.captures_iter(uri) {
+        let outer = cap.get(0).unwrap();
+        literal.push_str(&uri[offset..outer.start()]);
+        offset = outer.end();
+        match cap.get(1) {
+            Some(inner) => {
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Variable(inner.as_str()));
+            }
+            // "{{" or "}}"
+            None => literal.push_str(&outer.as_str()[..1]),
+        }
+    }
+    literal.push_str(&uri[offset..]);
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    segments
+}
+
+/// Whether the variable at the given index is the last one before the fragment
+fn is_before_fragment(segments: &[Segment], index: usize) -> bool {
+    match segments.get(index + 1) {
+        None => true,
+        Some(Segment::Literal(rest)) => rest.starts_with('#'),
+        Some(Segment::Variable(_)) => false,
+    }
+}
 
 impl Parse for UriTemplate {
     fn parse(input: ParseStream) -> syn::Result<Self> {
@@ -53,17 +97,99 @@ impl Parse for Assignment {
     }
 }
 
+/// The template and the URI to match against it
+struct MatchTemplate {
+    span: Span,
+    uri: String,
+    input: Expr,
+}
+
+impl Parse for MatchTemplate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let uri = input.parse::<LitStr>()?;
+        input.parse::<Token![,]>()?;
+        let value = input.parse::<Expr>()?;
+        if input.lookahead1().peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(Self {
+            span: uri.span(),
+            uri: uri.value(),
+            input: value,
+        })
+    }
+}
+
+/// The part of the URI a variable is in
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum UriPart {
+    Authority,
+    Path,
+    Query,
+    Fragment,
+}
+
+impl UriPart {
+    const ALL: &'static [UriPart] = &[
+        UriPart::Authority,
+        UriPart::Path,
+        UriPart::Query,
+        UriPart::Fragment,
+    ];
+
+    /// The part that follows the given start of the template
+    fn of(template_start: &str) -> UriPart {
+        if template_start.contains('#') {
+            UriPart::Fragment
+        } else if template_start.contains('?') {
+            UriPart::Query
+        } else if let Some(authority) = template_start.split_once("://").map(|(_, a)| a) {
+            if authority.contains('/') {
+                UriPart::Path
+            } else {
+                UriPart::Authority
+            }
+        } else {
+            UriPart::Path
+        }
+    }
+}
+
+impl Display for UriPart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            UriPart::Authority => "authority",
+            UriPart::Path => "path",
+            UriPart::Query => "query",
+            UriPart::Fragment => "fragment",
+        };
+        f.write_str(name)
+    }
+}
+
 pub fn proc_format_uri(input: TokenStream) -> TokenStream {
     try_proc_format_uri(input).unwrap_or_else(Error::into_compile_error)
 }
 
+pub fn proc_try_format_uri(input: TokenStream) -> TokenStream {
+    try_proc_try_format_uri(input).unwrap_or_else(Error::into_compile_error)
+}
+
+pub fn proc_uri(input: TokenStream) -> TokenStream {
+    try_proc_uri(input).unwrap_or_else(Error::into_compile_error)
+}
+
+pub fn proc_match_uri(input: TokenStream) -> TokenStream {
+    try_proc_match_uri(input).unwrap_or_else(Error::into_compile_error)
+}
+
 fn build_lookup_map(assignments: Vec<Assignment>) -> Result<BTreeMap<String, Assignment>, Error> {
     let mut lookup = BTreeMap::<String, Assignment>::new();
     for assignment in assignments {
         let name = assignment.name.to_string();
         if lookup.contains_key(&name) {
             return Err(Error::new(
-                name.span(),
+                assignment.name.span(),
                 format!("duplicate bindings for \"{name}\""),
             ));
         }
@@ -74,11 +200,95 @@ fn build_lookup_map(assignments: Vec<Assignment>) -> Result<BTreeMap<String, Ass
 
 fn try_proc_format_uri(input: TokenStream) -> Result<TokenStream, Error> {
     // Parse the input tokens into a syntax tree
+    build_uri(parse2::<UriTemplate>(input)?, false)
+}
+
+fn try_proc_try_format_uri(input: TokenStream) -> Result<TokenStream, Error> {
+    build_uri(parse2::<UriTemplate>(input)?, true)
+}
+
+fn try_proc_uri(input: TokenStream) -> Result<TokenStream, Error> {
+    let template = parse2::<UriTemplate>(input)?;
+    validate_static_parts(&template)?;
+    let uri = build_uri(template, false)?;
+    Ok(quote! {
+        uri::parse_uri(#uri)
+    })
+}
+
+/// Checks that the template only has characters allowed in a URI outside the variables
+fn validate_static_parts(template: &UriTemplate) -> Result<(), Error> {
+    for segment in split_template(&template.uri) {
+        let Segment::Literal(part) = segment else {
+            continue;
+        };
+        let mut chars = part.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                if hex.len() != 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(Error::new(
+                        template.span,
+                        format!("Invalid percent-encoding '%{hex}' in URI"),
+                    ));
+                }
+            } else if !(c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=".contains(c)) {
+                return Err(Error::new(
+                    template.span,
+                    format!("Character '{c}' is not allowed in a URI"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits a variable of the template into its name and format
+fn split_variable(variable: &str, span: Span) -> Result<(&str, Option<&str>), Error> {
+    let parts = variable.split(":").collect::<Vec<_>>();
+    match parts.len() {
+        1 => Ok((parts[0], None)),
+        2 => Ok((parts[0], Some(parts[1]))),
+        _ => Err(Error::new(
+            span,
+            format!("Only 1 or 2 parts are expected for variable identifiers, found '{variable}'"),
+        )),
+    }
+}
+
+fn unknown_format(format: &str, variable: &str, span: Span) -> Error {
+    Error::new(
+        span,
+        format!(
+            "Unrecognized variable format type {format} for {variable}. Did you mean 'raw', 'path', 'segment', 'query', 'query_value' or 'fragment'?"
+        ),
+    )
+}
+
+fn check_position(
+    variable: &str,
+    position: UriPart,
+    allowed: &[UriPart],
+    span: Span,
+) -> Result<(), Error> {
+    if !allowed.contains(&position) {
+        return Err(Error::new(
+            span,
+            format!("Variable {variable} can't be used in the {position} of the URI"),
+        ));
+    }
+    Ok(())
+}
+
+/// Generates a block that builds the URI as a `String`. A `fallible` block returns a
+/// `Result<String, uri::QueryError>` instead, and is the only one that can have
+/// `:query` variables.
+fn build_uri(template: UriTemplate, fallible: bool) -> Result<TokenStream, Error> {
     let UriTemplate {
         span,
         uri,
         assignments,
-    } = parse2::<UriTemplate>(input)?;
+    } = template;
 
     // Index binding values by their name
     let bindings = build_lookup_map(assignments)?;
@@ -90,34 +300,26 @@ fn try_proc_format_uri(input: TokenStream) -> Result<TokenStream, Error> {
         // Define builder that can be used to build the SQL instance
         quote! { let mut __uri = String::with_capacity(#capacity); },
     ];
-    let re = Regex::new(BINDING_RE).unwrap();
-    // Start offset for binding search
-    let mut offset = 0;
-    while let Some(cap) = re.captures_iter(&uri[offset..]).next() {
-        let outer = cap.get(0).unwrap(); // Outer capture: the whole binding including "${" and "}"
-        let inner = cap.get(1).unwrap(); // Inner capture: only the binding name
-        let binding_part = inner.as_str();
-        let binding_vec = binding_part.split(":").collect::<Vec<_>>();
-        let (binding, binding_type) = match binding_vec.len() {
-            1 => (binding_vec[0], None),
-            2 => (binding_vec[0], Some(binding_vec[1])),
-            _ => {
-                return Err(Error::new(
-                    span,
-                    format!(
-                        "Only 1 or 2 parts are expected for variable identifiers, found '{binding_part}'"
-                    ),
-                ));
+    // Whether any variable is converted into a string
+    let mut has_values = false;
+    // Whether the parameters of a `:query` variable are serialized, which can fail
+    let mut has_query = false;
+    // The literal text before the current variable
+    let mut prefix = String::new();
+    let segments = split_template(&uri);
+    for (index, segment) in segments.iter().enumerate() {
+        let binding_part = match segment {
+            Segment::Literal(literal) => {
+                statements.push(quote! {
+                    __uri.push_str(#literal);
+                });
+                prefix.push_str(literal);
+                continue;
             }
+            Segment::Variable(variable) => *variable,
         };
-
-        let uri_part = &uri[offset..offset + outer.start()];
-        offset += outer.end();
-        if !uri_part.is_empty() {
-            statements.push(quote! {
-                __uri.push_str(#uri_part);
-            });
-        }
+        let (binding, binding_type) = split_variable(binding_part, span)?;
+        let position = UriPart::of(&prefix);
         let value = bindings.get(binding).map_or_else(
             || {
                 let ident = Ident::new(binding, Span::call_site());
@@ -128,38 +330,139 @@ fn try_proc_format_uri(input: TokenStream) -> Result<TokenStream, Error> {
                 quote! { #val }
             },
         );
-        match binding_type {
-            Some("raw") => statements.push(quote! {
-                __uri.push_str(#value);
-            }),
-            None => statements.push(quote! {
-                __uri.push_str(&urlencoding::encode(#value));
-            }),
-            Some(x) => {
-                return Err(Error::new(
-                    span,
-                    format!(
-                        "Unrecognized variable format type {x} for {binding_part}. Did you mean 'raw'?"
-                    ),
-                ));
-            }
+        // Anything that is AsRef<str> or Display, see uri::UriValue
+        let string = quote! { (&uri::UriValue(&(#value))).uri_value() };
+        has_values |= binding_type != Some("query");
+        has_query |= binding_type == Some("query");
+        let (statement, allowed) = match binding_type {
+            Some("raw") => (quote! { __uri.push_str(&#string); }, UriPart::ALL),
+            None => (
+                quote! { __uri.push_str(&uri::encode_component(&#string)); },
+                UriPart::ALL,
+            ),
+            Some("path") => (
+                quote! { __uri.push_str(&uri::encode_path(&#string)); },
+                &[UriPart::Path][..],
+            ),
+            Some("segment") => (
+                quote! { __uri.push_str(&uri::encode_path_segment(&#string)); },
+                &[UriPart::Path][..],
+            ),
+            Some("query") => (
+                quote! { let __query = uri::push_query(&mut __uri, &(#value)); },
+                &[UriPart::Path, UriPart::Query][..],
+            ),
+            Some("query_value") => (
+                quote! { __uri.push_str(&uri::encode_query_value(&#string)); },
+                &[UriPart::Query][..],
+            ),
+            Some("fragment") => (
+                quote! { __uri.push_str(&uri::encode_fragment(&#string)); },
+                &[UriPart::Fragment][..],
+            ),
+            Some(x) => return Err(unknown_format(x, binding_part, span)),
         };
+        check_position(binding_part, position, allowed, span)?;
+        if binding_type == Some("query") && !fallible {
+            return Err(Error::new(
+                span,
+                format!("Variable {binding_part} can fail to serialize, use try_format_uri!"),
+            ));
+        }
+        if binding_type == Some("query") && !is_before_fragment(&segments, index) {
+            return Err(Error::new(
+                span,
+                format!(
+                    "Variable {binding_part} must be at the end of the URI or before the fragment"
+                ),
+            ));
+        }
+        statements.push(statement);
     }
-    // If template does not end with binding, we need to also push
-    // the last part of the template
-    if offset < uri.len() {
-        let remaining = &uri[offset..];
-        statements.push(quote! {
-            __uri.push_str(#remaining);
-        });
+    if has_values {
+        statements.insert(
+            0,
+            quote! { use uri::{AsRefUriValue as _, DisplayUriValue as _}; },
+        );
     }
 
     // All done, create final AST that create a block that builds the URI
     // instance using the statements and finally returns the encoded URI to the caller
+    let result = if has_query {
+        quote! { __query.map(|()| __uri) }
+    } else if fallible {
+        quote! { Ok::<String, uri::QueryError>(__uri) }
+    } else {
+        quote! { __uri }
+    };
     Ok(quote! {
         {
             #(#statements)*
-            __uri
+            #result
+        }
+    })
+}
+
+/// Generates a block that matches the input against the template and returns the
+/// captured variables
+fn try_proc_match_uri(input: TokenStream) -> Result<TokenStream, Error> {
+    let MatchTemplate { span, uri, input } = parse2::<MatchTemplate>(input)?;
+
+    let mut parts: Vec<TokenStream> = vec![];
+    let mut names: Vec<&str> = vec![];
+    let mut prefix = String::new();
+    for segment in split_template(&uri) {
+        let binding_part = match segment {
+            Segment::Literal(literal) => {
+                parts.push(quote! { uri::TemplatePart::Literal(#literal) });
+                prefix.push_str(&literal);
+                continue;
+            }
+            Segment::Variable(variable) => variable,
+        };
+        let (binding, binding_type) = split_variable(binding_part, span)?;
+        let position = UriPart::of(&prefix);
+        let (capture, allowed) = match binding_type {
+            Some("raw") => (quote! { uri::Capture::Raw }, UriPart::ALL),
+            None => (quote! { uri::Capture::Component }, UriPart::ALL),
+            Some("path") => (quote! { uri::Capture::Path }, &[UriPart::Path][..]),
+            Some("segment") => (quote! { uri::Capture::Segment }, &[UriPart::Path][..]),
+            Some("query_value") => (quote! { uri::Capture::QueryValue }, &[UriPart::Query][..]),
+            Some("fragment") => (quote! { uri::Capture::Fragment }, &[UriPart::Fragment][..]),
+            Some("query") => {
+                return Err(Error::new(
+                    span,
+                    format!("Variable {binding_part} can't be used with match_uri!"),
+                ));
+            }
+            Some(x) => return Err(unknown_format(x, binding_part, span)),
+        };
+        check_position(binding_part, position, allowed, span)?;
+        if names.contains(&binding) {
+            return Err(Error::new(
+                span,
+                format!("Variable {binding} is used more than once"),
+            ));
+        }
+        names.push(binding);
+        parts.push(quote! { uri::TemplatePart::Variable(#capture) });
+    }
+
+    let count = names.len();
+    let idents = names
+        .iter()
+        .map(|name| Ident::new(name, Span::call_site()))
+        .collect::<Vec<_>>();
+    let captures = match idents.as_slice() {
+        [ident] => quote! { #ident },
+        _ => quote! { (#(#idents),*) },
+    };
+    Ok(quote! {
+        {
+            use uri::{AsRefUriValue as _, DisplayUriValue as _};
+            const __PARTS: &[uri::TemplatePart] = &[#(#parts),*];
+            uri::match_template::<#count>(__PARTS, &(&uri::UriValue(&(#input))).uri_value())
+                .map(|[#(#idents),*]| #captures)
         }
     })
 }
@@ -170,7 +473,7 @@ mod tests {
     use proc_macro2::TokenStream;
     use quote::quote;
 
-    use super::proc_format_uri;
+    use super::{proc_format_uri, proc_match_uri, proc_try_format_uri, proc_uri};
 
     #[test]
     fn test_no_bindings() {
@@ -192,9 +495,10 @@ mod tests {
             stringify(proc_format_uri(quote! {"http://localhost/{path}"})),
             stringify(quote! {
                 {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
                     let mut __uri = String::with_capacity(46usize);
                     __uri.push_str("http://localhost/");
-                    __uri.push_str(&urlencoding::encode(path));
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&(path))).uri_value()));
                     __uri
                 }
             })
@@ -209,9 +513,10 @@ mod tests {
             )),
             stringify(quote! {
                 {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
                     let mut __uri = String::with_capacity(46usize);
                     __uri.push_str("http://localhost/");
-                    __uri.push_str(&urlencoding::encode("foo"));
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&("foo"))).uri_value()));
                     __uri
                 }
             })
@@ -226,11 +531,12 @@ mod tests {
             )),
             stringify(quote! {
                 {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
                     let mut __uri = String::with_capacity(84usize);
                     __uri.push_str("http://localhost/");
-                    __uri.push_str(&urlencoding::encode("foo"));
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&("foo"))).uri_value()));
                     __uri.push_str("/?param=");
-                    __uri.push_str(&urlencoding::encode(value));
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&(value))).uri_value()));
                     __uri.push_str("&a=b");
                     __uri
                 }
@@ -246,10 +552,11 @@ mod tests {
             )),
             stringify(quote! {
                 {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
                     let mut __uri = String::with_capacity(44usize);
-                    __uri.push_str(config.host);
+                    __uri.push_str(&(&uri::UriValue(&(config.host))).uri_value());
                     __uri.push_str("/");
-                    __uri.push_str(&urlencoding::encode("foo"));
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&("foo"))).uri_value()));
                     __uri.push_str("/?a=b");
                     __uri
                 }
@@ -257,6 +564,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_query_params() {
+        assert_eq!(
+            stringify(proc_try_format_uri(
+                quote! {"http://localhost/things{params:query}", params=&search}
+            )),
+            stringify(quote! {
+                {
+                    let mut __uri = String::with_capacity(74usize);
+                    __uri.push_str("http://localhost/things");
+                    let __query = uri::push_query(&mut __uri, &(&search));
+                    __query.map(|()| __uri)
+                }
+            })
+        );
+        assert_eq!(
+            stringify(proc_format_uri(
+                quote! {"http://localhost/things{params:query}", params=&search}
+            )),
+            stringify(quote! {
+                ::core::compile_error! {"Variable params:query can fail to serialize, use try_format_uri!"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_without_query_params() {
+        assert_eq!(
+            stringify(proc_try_format_uri(quote! {"http://localhost/things"})),
+            stringify(quote! {
+                {
+                    let mut __uri = String::with_capacity(46usize);
+                    __uri.push_str("http://localhost/things");
+                    Ok::<String, uri::QueryError>(__uri)
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_context_aware_formats() {
+        assert_eq!(
+            stringify(proc_format_uri(
+                quote! {"http://localhost/{dir:path}/{name:segment}?q={q:query_value}#{f:fragment}"}
+            )),
+            stringify(quote! {
+                {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
+                    let mut __uri = String::with_capacity(146usize);
+                    __uri.push_str("http://localhost/");
+                    __uri.push_str(&uri::encode_path(&(&uri::UriValue(&(dir))).uri_value()));
+                    __uri.push_str("/");
+                    __uri.push_str(&uri::encode_path_segment(&(&uri::UriValue(&(name))).uri_value()));
+                    __uri.push_str("?q=");
+                    __uri.push_str(&uri::encode_query_value(&(&uri::UriValue(&(q))).uri_value()));
+                    __uri.push_str("#");
+                    __uri.push_str(&uri::encode_fragment(&(&uri::UriValue(&(f))).uri_value()));
+                    __uri
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_fails_with_misplaced_format() {
+        assert_eq!(
+            stringify(proc_format_uri(quote! {"http://localhost/{a:query_value}"})),
+            stringify(quote! {
+                ::core::compile_error! {"Variable a:query_value can't be used in the path of the URI"}
+            })
+        );
+        assert_eq!(
+            stringify(proc_format_uri(quote! {"http://{host:path}/"})),
+            stringify(quote! {
+                ::core::compile_error! {"Variable host:path can't be used in the authority of the URI"}
+            })
+        );
+        assert_eq!(
+            stringify(proc_try_format_uri(quote! {"/things{params:query}/more"})),
+            stringify(quote! {
+                ::core::compile_error! {"Variable params:query must be at the end of the URI or before the fragment"}
+            })
+        );
+    }
+
     #[test]
     fn test_fails_with_multiple_colons() {
         assert_eq!(
@@ -283,6 +675,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_escaped_braces() {
+        assert_eq!(
+            stringify(proc_format_uri(
+                quote! {"http://localhost/{{name}}/{name}}}"}
+            )),
+            stringify(quote! {
+                {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
+                    let mut __uri = String::with_capacity(68usize);
+                    __uri.push_str("http://localhost/{name}/");
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&(name))).uri_value()));
+                    __uri.push_str("}");
+                    __uri
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces_after_query() {
+        assert_eq!(
+            stringify(proc_try_format_uri(quote! {"/things{params:query}}}"})),
+            stringify(quote! {
+                ::core::compile_error! {"Variable params:query must be at the end of the URI or before the fragment"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_match_uri_escaped_braces() {
+        assert_eq!(
+            stringify(proc_match_uri(quote! {"/{{id}}/{id}", input})),
+            stringify(quote! {
+                {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
+                    const __PARTS: &[uri::TemplatePart] = &[
+                        uri::TemplatePart::Literal("/{id}/"),
+                        uri::TemplatePart::Variable(uri::Capture::Component)
+                    ];
+                    uri::match_template::<1usize>(__PARTS, &(&uri::UriValue(&(input))).uri_value())
+                        .map(|[id]| id)
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_uri() {
+        assert_eq!(
+            stringify(proc_uri(quote! {"http://localhost/things/{id}"})),
+            stringify(quote! {
+                uri::parse_uri({
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
+                    let mut __uri = String::with_capacity(56usize);
+                    __uri.push_str("http://localhost/things/");
+                    __uri.push_str(&uri::encode_component(&(&uri::UriValue(&(id))).uri_value()));
+                    __uri
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_uri_fails_with_invalid_static_parts() {
+        assert_eq!(
+            stringify(proc_uri(quote! {"http://localhost/some things/{id}"})),
+            stringify(quote! {
+                ::core::compile_error! {"Character ' ' is not allowed in a URI"}
+            })
+        );
+        assert_eq!(
+            stringify(proc_uri(quote! {"http://localhost/100%/{id}"})),
+            stringify(quote! {
+                ::core::compile_error! {"Invalid percent-encoding '%/' in URI"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_match_uri() {
+        assert_eq!(
+            stringify(proc_match_uri(
+                quote! {"http://host/{a}/x?p={b:query_value}", input}
+            )),
+            stringify(quote! {
+                {
+                    use uri::{AsRefUriValue as _, DisplayUriValue as _};
+                    const __PARTS: &[uri::TemplatePart] = &[
+                        uri::TemplatePart::Literal("http://host/"),
+                        uri::TemplatePart::Variable(uri::Capture::Component),
+                        uri::TemplatePart::Literal("/x?p="),
+                        uri::TemplatePart::Variable(uri::Capture::QueryValue)
+                    ];
+                    uri::match_template::<2usize>(__PARTS, &(&uri::UriValue(&(input))).uri_value())
+                        .map(|[a, b]| (a, b))
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_match_uri_fails_with_repeated_variable() {
+        assert_eq!(
+            stringify(proc_match_uri(quote! {"/{a}/{a}", input})),
+            stringify(quote! {
+                ::core::compile_error! {"Variable a is used more than once"}
+            })
+        );
+    }
+
     #[allow(clippy::needless_pass_by_value)]
     fn stringify(s: TokenStream) -> String {
         format!("{s}")
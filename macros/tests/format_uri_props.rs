@@ -0,0 +1,25 @@
+use macros::format_uri;
+use proptest::prelude::*;
+
+fn is_encoded(part: &str) -> bool {
+    part.bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"-._~%".contains(&b))
+}
+
+proptest! {
+    #[test]
+    fn test_bound_values_round_trip(path in any::<String>(), param in any::<String>()) {
+        let uri = format_uri!(
+            "http://localhost/things/{path}?param={param}",
+            path = path.as_str(),
+            param = param.as_str()
+        );
+
+        let rest = uri.strip_prefix("http://localhost/things/").unwrap();
+        let (path_part, param_part) = rest.split_once("?param=").unwrap();
+        prop_assert!(is_encoded(path_part), "{path_part}");
+        prop_assert!(is_encoded(param_part), "{param_part}");
+        prop_assert_eq!(urlencoding::decode(path_part).unwrap(), path);
+        prop_assert_eq!(urlencoding::decode(param_part).unwrap(), param);
+    }
+}
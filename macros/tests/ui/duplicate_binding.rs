@@ -0,0 +1,5 @@
+use macros::format_uri;
+
+fn main() {
+    let _uri = format_uri!("http://localhost/{path}", path = "a", path = "b");
+}
@@ -0,0 +1,6 @@
+use macros::format_uri;
+
+fn main() {
+    let params = [("page", 1)];
+    let _uri = format_uri!("http://localhost/things{params:query}");
+}
@@ -0,0 +1,8 @@
+use macros::match_uri;
+
+fn main() {
+    let _params = match_uri!(
+        "http://localhost/things{params:query}",
+        "http://localhost/things"
+    );
+}
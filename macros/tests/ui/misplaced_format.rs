@@ -0,0 +1,6 @@
+use macros::format_uri;
+
+fn main() {
+    let dir = "docs/reports";
+    let _uri = format_uri!("http://localhost/files?dir={dir:path}");
+}
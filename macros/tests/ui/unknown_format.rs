@@ -0,0 +1,6 @@
+use macros::format_uri;
+
+fn main() {
+    let host = "http://localhost";
+    let _uri = format_uri!("{host:verbatim}/path");
+}
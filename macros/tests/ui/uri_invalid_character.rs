@@ -0,0 +1,6 @@
+use macros::uri;
+
+fn main() {
+    let id = 1;
+    let _uri = uri!("http://localhost/some things/{id}");
+}
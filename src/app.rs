@@ -1,7 +1,8 @@
 mod api_error;
 mod extractors;
 mod models;
-mod routes;
+pub(crate) mod openapi;
+pub(crate) mod routes;
 mod server;
 
 pub use server::*;
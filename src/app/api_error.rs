@@ -1,9 +1,10 @@
 use std::error::Error;
 use std::fmt::Display;
 
+use api_client::ApiErrorCode;
 use axum::http::{header, StatusCode};
-use axum::Json;
 use axum::response::{IntoResponse, Response};
+use axum::Json;
 use serde_json::{json, Value};
 use tracing::error;
 
@@ -19,20 +20,20 @@ impl ApiError {
     pub fn not_found() -> Self {
         Self {
             http_status: StatusCode::NOT_FOUND,
-            body: json!({ "error": "not_found" }),
+            body: json!({ "error": ApiErrorCode::NotFound }),
         }
     }
     pub fn invalid_path_param() -> Self {
         Self {
             http_status: StatusCode::BAD_REQUEST,
-            body: json!({ "error": "invalid_path_param" }),
+            body: json!({ "error": ApiErrorCode::InvalidPathParam }),
         }
     }
     pub fn internal(e: InternalError) -> Self {
         error!("{e}");
         Self {
             http_status: StatusCode::INTERNAL_SERVER_ERROR,
-            body: json!({ "error": "internal_server_error" }),
+            body: json!({ "error": ApiErrorCode::InternalServerError }),
         }
     }
 }
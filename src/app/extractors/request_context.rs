@@ -2,7 +2,7 @@ use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
 
 use crate::app::api_error::ApiError;
-use crate::context::{Environment, RootContext};
+use crate::context::{Environment, RequestTrace, RootContext};
 
 pub struct RequestContext(pub RootContext);
 
@@ -14,7 +14,12 @@ impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
             .extensions
             .get::<Environment>()
             .expect("Environment missing");
-        let ctx = RootContext::new(env.clone());
+        let trace = parts
+            .extensions
+            .get::<RequestTrace>()
+            .cloned()
+            .unwrap_or_default();
+        let ctx = RootContext::with_trace(env.clone(), trace);
         Ok(RequestContext(ctx))
     }
 }
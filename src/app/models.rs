@@ -1,3 +1,7 @@
+mod health;
 mod thing;
 
-pub use thing::*;
+pub use api_client::{
+    ApiErrorBody, ApiThing, ApiThingData, HealthCheckReport, HealthResponse, HealthStatus,
+    RootResponse,
+};
@@ -0,0 +1,31 @@
+use tracing::error;
+
+use crate::app::models::HealthCheckReport;
+use crate::health::HealthCheckOutcome;
+
+/// Reported instead of the error of a failed check, as it may reveal internals such as
+/// the address of the database. The error itself is logged.
+const HEALTH_CHECK_FAILED: &str = "Health check failed";
+
+impl From<HealthCheckOutcome> for HealthCheckReport {
+    fn from(x: HealthCheckOutcome) -> Self {
+        let duration_ms = x.duration.as_millis() as u64;
+        match x.result {
+            Ok(details) => Self {
+                healthy: true,
+                duration_ms,
+                details,
+                error: None,
+            },
+            Err(e) => {
+                error!("Health check {} failed: {e}", x.name);
+                Self {
+                    healthy: false,
+                    duration_ms,
+                    details: None,
+                    error: Some(HEALTH_CHECK_FAILED.to_string()),
+                }
+            }
+        }
+    }
+}
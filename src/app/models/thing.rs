@@ -1,24 +1,7 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
-
+use crate::app::models::{ApiThing, ApiThingData};
 use crate::db::DbThing;
 use crate::service::ThingData;
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct ApiThingData {
-    pub name: String,
-    pub description: Option<String>,
-}
-
-#[derive(Serialize, Debug)]
-pub struct ApiThing {
-    id: Uuid,
-    name: String,
-    description: Option<String>,
-    created_at: DateTime<Utc>,
-}
-
 impl From<DbThing> for ApiThing {
     fn from(x: DbThing) -> Self {
         Self {
@@ -0,0 +1,23 @@
+use utoipa::OpenApi;
+
+use crate::app::routes::get_root::__path_get_root_route_handler;
+use crate::app::routes::health::{__path_get_live_handler, __path_get_ready_handler};
+use crate::app::routes::thing::{
+    __path_delete_thing_handler, __path_get_thing_handler, __path_post_thing_handler,
+};
+
+/// The OpenAPI document of the API, built from the `utoipa::path` attributes of the
+/// handlers and the models they refer to
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rust-server"),
+    paths(
+        get_root_route_handler,
+        get_live_handler,
+        get_ready_handler,
+        post_thing_handler,
+        get_thing_handler,
+        delete_thing_handler,
+    )
+)]
+pub struct ApiDoc;
@@ -1,16 +1,69 @@
+use axum::handler::Handler;
+use axum::routing::{on, MethodFilter};
 use axum::Router;
-use axum::routing::{delete, get, post};
+use http::Method;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
+use crate::app::openapi::ApiDoc;
 use crate::app::routes::get_root::get_root_route_handler;
+use crate::app::routes::health::{get_live_handler, get_ready_handler};
 use crate::app::routes::thing::{delete_thing_handler, get_thing_handler, post_thing_handler};
 
-mod get_root;
-mod thing;
+pub(crate) mod get_root;
+pub(crate) mod health;
+pub(crate) mod thing;
 
+/// The routes of the API, the OpenAPI document at `/openapi.json` and its docs at `/docs`
 pub fn create_routes() -> Router {
-    Router::new()
-        .route("/", get(get_root_route_handler))
-        .route("/things", post(post_thing_handler))
-        .route("/things/{thing_id}", get(get_thing_handler))
-        .route("/things/{thing_id}", delete(delete_thing_handler))
+    let docs = SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi());
+    api_routes().into_router().merge(docs)
+}
+
+/// Every route of the API. Each of them must be in the [`ApiDoc`].
+pub fn api_routes() -> ApiRoutes {
+    ApiRoutes::new()
+        .route(Method::GET, "/", get_root_route_handler)
+        .route(Method::GET, "/health/live", get_live_handler)
+        .route(Method::GET, "/health/ready", get_ready_handler)
+        .route(Method::POST, "/things", post_thing_handler)
+        .route(Method::GET, "/things/{thing_id}", get_thing_handler)
+        .route(Method::DELETE, "/things/{thing_id}", delete_thing_handler)
+}
+
+/// A router that keeps track of the method and path of its routes, so that they can be
+/// compared with the OpenAPI document
+pub struct ApiRoutes {
+    router: Router,
+    routes: Vec<(Method, &'static str)>,
+}
+
+impl ApiRoutes {
+    fn new() -> Self {
+        ApiRoutes {
+            router: Router::new(),
+            routes: vec![],
+        }
+    }
+
+    fn route<H: Handler<T, ()>, T: 'static>(
+        mut self,
+        method: Method,
+        path: &'static str,
+        handler: H,
+    ) -> Self {
+        let filter = MethodFilter::try_from(method.clone()).expect("Unsupported method");
+        self.router = self.router.route(path, on(filter, handler));
+        self.routes.push((method, path));
+        self
+    }
+
+    #[cfg(test)]
+    pub fn routes(&self) -> &[(Method, &'static str)] {
+        &self.routes
+    }
+
+    pub fn into_router(self) -> Router {
+        self.router
+    }
 }
@@ -1,14 +1,13 @@
 use crate::app::extractors::RequestContext;
+use crate::app::models::RootResponse;
 use crate::context::Context;
 use axum::Json;
-use serde::Serialize;
-
-#[derive(Serialize, Debug)]
-pub struct RootResponse {
-    status: String,
-    env: String,
-}
 
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, description = "The server is up", body = RootResponse)),
+)]
 pub async fn get_root_route_handler(RequestContext(ctx): RequestContext) -> Json<RootResponse> {
     Json(RootResponse {
         status: "ok".to_string(),
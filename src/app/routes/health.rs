@@ -0,0 +1,5 @@
+mod get_live;
+mod get_ready;
+
+pub use get_live::*;
+pub use get_ready::*;
@@ -0,0 +1,18 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+
+use crate::app::models::{HealthResponse, HealthStatus};
+
+/// Succeeds while the process is up, even while it is shutting down
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    responses((status = 200, description = "The process is up", body = HealthResponse)),
+)]
+pub async fn get_live_handler() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: HealthStatus::Ok,
+        checks: BTreeMap::new(),
+    })
+}
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use http::StatusCode;
+
+use crate::app::extractors::RequestContext;
+use crate::app::models::{HealthCheckReport, HealthResponse, HealthStatus};
+use crate::context::Context;
+
+/// Runs the health checks of the environment. Fails while the server is shutting down,
+/// without running them.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "All checks succeeded", body = HealthResponse),
+        (status = 503, description = "A check failed or the server is shutting down", body = HealthResponse),
+    ),
+)]
+pub async fn get_ready_handler(
+    RequestContext(ctx): RequestContext,
+) -> (StatusCode, Json<HealthResponse>) {
+    let env = ctx.env();
+    if env.shutdown.is_shutting_down() {
+        let response = HealthResponse {
+            status: HealthStatus::ShuttingDown,
+            checks: BTreeMap::new(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    }
+    let checks: BTreeMap<String, HealthCheckReport> = env
+        .health_checks
+        .run(env)
+        .await
+        .into_iter()
+        .map(|outcome| (outcome.name.clone(), outcome.into()))
+        .collect();
+    let (status, code) = if checks.values().all(|check| check.healthy) {
+        (HealthStatus::Ok, StatusCode::OK)
+    } else {
+        (HealthStatus::Unavailable, StatusCode::SERVICE_UNAVAILABLE)
+    };
+    (code, Json(HealthResponse { status, checks }))
+}
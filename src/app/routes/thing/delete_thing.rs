@@ -4,10 +4,22 @@ use uuid::Uuid;
 
 use crate::app::api_error::ApiError;
 use crate::app::extractors::{InputPath, RequestContext};
+use crate::app::models::ApiErrorBody;
 use crate::context::Context;
 use crate::context::Transactional;
 use crate::service::delete_thing;
 
+/// Deletes the thing, if it exists
+#[utoipa::path(
+    delete,
+    path = "/things/{thing_id}",
+    params(("thing_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The thing no longer exists"),
+        (status = 400, description = "The id is not a UUID", body = ApiErrorBody),
+        (status = 500, body = ApiErrorBody),
+    ),
+)]
 pub async fn delete_thing_handler(
     RequestContext(mut ctx): RequestContext,
     InputPath(thing_id): InputPath<Uuid>,
@@ -1,10 +1,21 @@
 use crate::app::api_error::ApiError;
 use crate::app::extractors::{InputPath, RequestContext};
-use crate::app::models::ApiThing;
+use crate::app::models::{ApiErrorBody, ApiThing};
 use crate::service::find_thing;
 use axum::Json;
 use uuid::Uuid;
 
+#[utoipa::path(
+    get,
+    path = "/things/{thing_id}",
+    params(("thing_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The thing", body = ApiThing),
+        (status = 400, description = "The id is not a UUID", body = ApiErrorBody),
+        (status = 404, description = "There is no thing with the id", body = ApiErrorBody),
+        (status = 500, body = ApiErrorBody),
+    ),
+)]
 pub async fn get_thing_handler(
     RequestContext(mut ctx): RequestContext,
     InputPath(thing_id): InputPath<Uuid>,
@@ -2,10 +2,19 @@ use axum::Json;
 
 use crate::app::api_error::ApiError;
 use crate::app::extractors::RequestContext;
-use crate::app::models::{ApiThing, ApiThingData};
+use crate::app::models::{ApiErrorBody, ApiThing, ApiThingData};
 use crate::context::{Context, Transactional};
 use crate::service::add_new_thing;
 
+#[utoipa::path(
+    post,
+    path = "/things",
+    request_body = ApiThingData,
+    responses(
+        (status = 200, description = "The new thing", body = ApiThing),
+        (status = 500, body = ApiErrorBody),
+    ),
+)]
 pub async fn post_thing_handler(
     RequestContext(mut ctx): RequestContext,
     Json(thing_data): Json<ApiThingData>,
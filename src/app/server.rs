@@ -1,20 +1,153 @@
 use crate::app::routes::create_routes;
-use crate::context::Environment;
-use axum::extract::{MatchedPath, Request};
+use crate::context::{Environment, RequestTrace, Shutdown, REQUEST_ID_HEADER};
+use axum::extract::{MatchedPath, Request, State};
 use axum::http::{header, StatusCode};
-use http::Response;
+use axum::middleware::{self, Next};
+use http::{HeaderValue, Response};
 
 use crate::error::InternalError;
-use axum::Extension;
+use axum::{Extension, Router};
 use bytes::Bytes;
 use http_body_util::Full;
 use std::any::Any;
+use std::future::IntoFuture;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
 use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{error, info, warn};
 
-pub async fn start_server(env: Environment) -> Result<(), InternalError> {
-    let app = create_routes()
+pub async fn start_server(env: Environment) -> Result<ShutdownSummary, InternalError> {
+    let app = create_app(env.clone());
+
+    let addr = format!("0.0.0.0:{}", env.config.server.port);
+    // Address to run our server on
+    info!("Listening on {addr}");
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(InternalError::from)?;
+    tokio::spawn(shutdown_on_signal(env.shutdown.clone()));
+    // Run the server
+    let timeout = Duration::from_millis(env.config.server.shutdown_timeout_ms);
+    let summary = serve(listener, app, &env.shutdown, timeout).await?;
+    close_environment(&env, timeout.saturating_sub(summary.drain_time)).await;
+    info!(
+        "Server stopped after {} requests, drained in {} ms, {} in-flight requests abandoned",
+        summary.completed,
+        summary.drain_time.as_millis(),
+        summary.abandoned
+    );
+    Ok(summary)
+}
+
+/// Closes the database pools, waiting at most `timeout` for the connections in use.
+/// Abandoned requests may hold on to their connections until the process exits.
+/// Returns whether the pools were closed.
+pub async fn close_environment(env: &Environment, timeout: Duration) -> bool {
+    let closed = tokio::time::timeout(timeout, env.close()).await.is_ok();
+    if !closed {
+        warn!("Timed out closing the database pools");
+    }
+    closed
+}
+
+/// How the server stopped
+#[derive(Debug, Clone, Copy)]
+pub struct ShutdownSummary {
+    /// Requests handled, including the ones drained during the shutdown
+    pub completed: u64,
+    /// Requests still in flight when the shutdown timed out. They are not cancelled, but
+    /// end with the process.
+    pub abandoned: usize,
+    /// Time from the start of the shutdown until the server stopped
+    pub drain_time: Duration,
+}
+
+impl ShutdownSummary {
+    /// Whether all in-flight requests were completed
+    pub fn is_clean(&self) -> bool {
+        self.abandoned == 0
+    }
+}
+
+/// Serves the app until the shutdown is started. Then stops accepting connections and
+/// waits up to `timeout` for the requests in flight.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    shutdown: &Shutdown,
+    timeout: Duration,
+) -> Result<ShutdownSummary, InternalError> {
+    let signal = shutdown.clone();
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { signal.started().await })
+        .into_future();
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => result.map_err(InternalError::from)?,
+        _ = shutdown.started() => {},
+    }
+    let draining = Instant::now();
+    info!(
+        "Shutting down, waiting up to {} ms for {} in-flight requests",
+        timeout.as_millis(),
+        shutdown.in_flight()
+    );
+    match tokio::time::timeout(timeout, &mut server).await {
+        Ok(result) => result.map_err(InternalError::from)?,
+        Err(_) => warn!(
+            "Shutdown timed out with {} requests in flight",
+            shutdown.in_flight()
+        ),
+    }
+    Ok(ShutdownSummary {
+        completed: shutdown.completed(),
+        abandoned: shutdown.in_flight(),
+        drain_time: draining.elapsed(),
+    })
+}
+
+/// Starts the shutdown on SIGTERM or SIGINT
+async fn shutdown_on_signal(shutdown: Shutdown) {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Can't listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!("Can't listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    let signal = tokio::select! {
+        _ = interrupt => "SIGINT",
+        _ = terminate => "SIGTERM",
+    };
+    info!("Received {signal}");
+    shutdown.start();
+}
+
+/// Builds the application: all routes with the layers of [`add_layers`]
+pub fn create_app(env: Environment) -> Router {
+    add_layers(create_routes(), env)
+}
+
+/// Adds request tracing, the `Environment` extension, panic handling and the
+/// [`RequestTrace`] of each request to the routes
+pub fn add_layers(routes: Router, env: Environment) -> Router {
+    routes
         .layer(
             TraceLayer::new_for_http()
                 // Create our own span for the request and include the matched path. The matched
@@ -29,27 +162,47 @@ pub async fn start_server(env: Environment) -> Result<(), InternalError> {
                         .get::<MatchedPath>()
                         .map(|matched_path| matched_path.as_str());
 
-                    tracing::debug_span!("request", %method, %uri, matched_path)
+                    let request_id = req
+                        .extensions()
+                        .get::<RequestTrace>()
+                        .map(|trace| trace.request_id.as_str());
+
+                    tracing::debug_span!("request", %method, %uri, matched_path, request_id)
                 })
                 // By default, `TraceLayer` will log 5xx responses, but we're doing our specific
                 // logging of errors so disable that
                 .on_failure(()),
         )
         .layer(Extension(env.clone()))
-        .layer(CatchPanicLayer::custom(handle_panic));
+        .layer(CatchPanicLayer::custom(handle_panic))
+        .layer(middleware::from_fn_with_state(
+            env.shutdown.clone(),
+            track_request,
+        ))
+        .layer(middleware::from_fn(trace_request))
+}
 
-    let addr = format!("0.0.0.0:{}", env.config.server.port);
-    // Address to run our server on
-    info!("Listening on {addr}");
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .map_err(InternalError::from)?;
-    // Run the server
-    axum::serve(listener, app)
-        .await
-        .map_err(InternalError::from)?;
-    info!("Server stopped");
-    Ok(())
+/// Counts the request as in flight in the [`Shutdown`] while it is handled
+async fn track_request(
+    State(shutdown): State<Shutdown>,
+    req: Request,
+    next: Next,
+) -> axum::response::Response {
+    let _guard = shutdown.track_request();
+    next.run(req).await
+}
+
+/// Makes the [`RequestTrace`] of the request available to the handlers and returns its
+/// request id in the response
+async fn trace_request(mut req: Request, next: Next) -> axum::response::Response {
+    let trace = RequestTrace::from_headers(req.headers());
+    let request_id = HeaderValue::from_str(&trace.request_id);
+    req.extensions_mut().insert(trace);
+    let mut res = next.run(req).await;
+    if let Ok(request_id) = request_id {
+        res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    }
+    res
 }
 
 fn handle_panic(_: Box<dyn Any + Send + 'static>) -> Response<Full<Bytes>> {
@@ -2,7 +2,13 @@ mod config;
 #[allow(clippy::module_inception)]
 mod context;
 mod env;
+mod shutdown;
+mod trace;
+mod tx_hooks;
 
 pub use config::*;
 pub use context::*;
 pub use env::*;
+pub use shutdown::*;
+pub use trace::*;
+pub use tx_hooks::*;
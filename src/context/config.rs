@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
+
 use config::builder::DefaultState;
 use config::{Config as ConfigCrate, ConfigBuilder};
 use config::{ConfigError, Environment as ConfigEnvironment, File};
+use http_client::HttpClientSettings;
 use serde::Deserialize;
 
 use crate::error::InternalError;
@@ -10,16 +13,51 @@ pub struct Config {
     pub environment_name: String,
     pub server: ServerSettings,
     pub database: DatabaseSettings,
+    /// Services called through an `HttpClient`, by name
+    #[serde(default)]
+    pub http_clients: BTreeMap<String, HttpClientSettings>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerSettings {
     pub port: u16,
+    /// How long the server may take to stop after a shutdown signal, waiting for the
+    /// in-flight requests and then for the database pools to close
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+    /// How long each check of `GET /health/ready` may take
+    #[serde(default = "default_health_check_timeout_ms")]
+    pub health_check_timeout_ms: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseSettings {
     pub url: String,
+    /// Streaming replicas used for read-only queries outside transactions
+    #[serde(default)]
+    pub replica_urls: Vec<String>,
+    #[serde(default)]
+    pub replica_selection: ReplicaSelection,
+    /// Run all queries of a request on a single connection, so that session state
+    /// (such as settings changed with `SET`) is kept between them
+    #[serde(default)]
+    pub pin_request_connection: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicaSelection {
+    #[default]
+    RoundRobin,
+    LeastConnections,
+}
+
+fn default_shutdown_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_health_check_timeout_ms() -> u64 {
+    2_000
 }
 
 impl Config {
@@ -1,33 +1,111 @@
 use std::future::Future;
+use std::time::Duration;
 
-use crate::context::Environment;
-use crate::db::{DatabaseAccess, DatabasePool, TransactionalConnection};
+use http_client::PropagatedHeaders;
+
+use crate::context::{ContextHooks, Environment, RequestTrace};
+use crate::db::{
+    AdvisoryLock, DatabaseAccess, DatabasePool, DatabaseReadAccess, LockKey, PinnableConnection,
+    SessionSettings, TransactionalConnection,
+};
 use crate::error::InternalError;
 
-pub trait Context: Send + Sync {
+/// The [`PropagatedHeaders`] identify the request this context is used for, in the
+/// requests made to other services.
+pub trait Context: PropagatedHeaders + Send + Sync {
     fn env(&self) -> &Environment;
 
-    fn db(&mut self) -> &mut impl DatabaseAccess;
+    /// Read-only database access. Writes require a transaction, see [`Transactional::db_mut`].
+    fn db(&mut self) -> &mut impl DatabaseReadAccess;
 
     fn begin(
         &mut self,
     ) -> impl Future<Output = Result<impl Context + Transactional, InternalError>> + Send;
+
+    /// Sets a Postgres configuration parameter (such as `app.user_id`) for every
+    /// transaction begun from this context. Inside a transaction it is set right away,
+    /// until the transaction ends. The value is always bound as a parameter.
+    fn set_session_setting(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> impl Future<Output = Result<(), InternalError>> + Send;
+
+    /// Tries to take a session-level advisory lock without waiting.
+    /// See [`DatabasePool::try_advisory_lock`].
+    fn try_advisory_lock(
+        &self,
+        key: impl Into<LockKey>,
+    ) -> impl Future<Output = Result<Option<AdvisoryLock>, InternalError>> + Send {
+        self.env().db_pool.try_advisory_lock(key.into())
+    }
+
+    /// Takes a session-level advisory lock, waiting at most `timeout` for it.
+    /// See [`DatabasePool::advisory_lock`].
+    fn advisory_lock(
+        &self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<AdvisoryLock, InternalError>> + Send {
+        self.env().db_pool.advisory_lock(key.into(), timeout)
+    }
 }
 
 pub trait Transactional: Send + Sync {
     fn commit(self) -> impl Future<Output = Result<(), InternalError>> + Send;
     fn rollback(self) -> impl Future<Output = Result<(), InternalError>> + Send;
+
+    /// Read-write database access within this transaction
+    fn db_mut(&mut self) -> &mut impl DatabaseAccess;
+
+    /// Registers a callback that is run after the outermost transaction has been committed.
+    /// Callbacks registered in a savepoint that is rolled back are discarded.
+    fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static);
+
+    /// Registers a callback that is run after the outermost transaction has been rolled
+    /// back, or dropped without being committed. Callbacks registered in a savepoint that
+    /// is rolled back are discarded.
+    fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static);
 }
 
 pub struct RootContext {
     env: Environment,
+    trace: RequestTrace,
     pool: DatabasePool,
+    read_db: PinnableConnection,
+    session_settings: SessionSettings,
 }
 
 impl RootContext {
     pub fn new(env: Environment) -> Self {
+        Self::with_trace(env, RequestTrace::new())
+    }
+
+    pub fn with_trace(env: Environment, trace: RequestTrace) -> Self {
         let pool = env.db_pool.clone();
-        RootContext { env, pool }
+        let read_db = PinnableConnection::new(
+            env.db_read_pool.clone(),
+            env.config.database.pin_request_connection,
+        );
+        RootContext {
+            env,
+            trace,
+            pool,
+            read_db,
+            session_settings: SessionSettings::new(),
+        }
+    }
+
+    /// Reads through this context go to the primary instead of a replica, so that
+    /// they are guaranteed to see previously committed writes
+    pub fn use_primary_for_reads(&mut self) {
+        self.read_db.use_primary();
+    }
+}
+
+impl PropagatedHeaders for RootContext {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.trace.propagated_headers()
     }
 }
 
@@ -36,22 +114,43 @@ impl Context for RootContext {
         &self.env
     }
 
-    fn db(&mut self) -> &mut impl DatabaseAccess {
-        &mut self.pool
+    fn db(&mut self) -> &mut impl DatabaseReadAccess {
+        &mut self.read_db
     }
 
     async fn begin(&mut self) -> Result<impl Context + Transactional, InternalError> {
-        let db = TransactionalConnection::begin_from_pool(&self.pool).await?;
+        let db = if self.read_db.is_pinned() {
+            let conn = self.read_db.connection().await?;
+            TransactionalConnection::begin_from_connection(conn, &self.session_settings).await?
+        } else {
+            TransactionalConnection::begin_from_pool(&self.pool, &self.session_settings).await?
+        };
         Ok(TxContext {
             env: self.env.clone(),
+            trace: self.trace.clone(),
             tx: db,
+            hooks: ContextHooks::outermost(),
         })
     }
+
+    async fn set_session_setting(&mut self, name: &str, value: &str) -> Result<(), InternalError> {
+        self.session_settings
+            .insert(name.to_string(), value.to_string());
+        Ok(())
+    }
 }
 
 pub struct TxContext<'a> {
     env: Environment,
+    trace: RequestTrace,
     tx: TransactionalConnection<'a>,
+    hooks: ContextHooks<'a>,
+}
+
+impl PropagatedHeaders for TxContext<'_> {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.trace.propagated_headers()
+    }
 }
 
 impl Context for TxContext<'_> {
@@ -59,7 +158,7 @@ impl Context for TxContext<'_> {
         &self.env
     }
 
-    fn db(&mut self) -> &mut impl DatabaseAccess {
+    fn db(&mut self) -> &mut impl DatabaseReadAccess {
         &mut self.tx
     }
 
@@ -67,17 +166,46 @@ impl Context for TxContext<'_> {
         let tx = self.tx.begin().await?;
         Ok(TxContext {
             env: self.env.clone(),
+            trace: self.trace.clone(),
             tx,
+            hooks: ContextHooks::savepoint(&mut self.hooks),
         })
     }
+
+    async fn set_session_setting(&mut self, name: &str, value: &str) -> Result<(), InternalError> {
+        self.tx.set_session_setting(name, value).await
+    }
 }
 
 impl Transactional for TxContext<'_> {
     async fn commit(self) -> Result<(), InternalError> {
-        self.tx.commit().await
+        match self.tx.commit().await {
+            Ok(()) => {
+                self.hooks.committed();
+                Ok(())
+            }
+            Err(e) => {
+                self.hooks.rolled_back();
+                Err(e)
+            }
+        }
     }
 
     async fn rollback(self) -> Result<(), InternalError> {
-        self.tx.rollback().await
+        let result = self.tx.rollback().await;
+        self.hooks.rolled_back();
+        result
+    }
+
+    fn db_mut(&mut self) -> &mut impl DatabaseAccess {
+        &mut self.tx
+    }
+
+    fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_commit(hook);
+    }
+
+    fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_rollback(hook);
     }
 }
@@ -1,15 +1,30 @@
 use std::env;
 
+use futures_core::Stream;
+use http_client::HttpClients;
+use serde::de::DeserializeOwned;
 use tracing::debug;
 
-use crate::context::Config;
-use crate::db::DatabasePool;
+use crate::context::{Config, Shutdown};
+use crate::db::{DatabaseConnection, DatabasePool, DatabaseReadPool, DbNotification, PoolUsage};
 use crate::error::InternalError;
+use crate::health::HealthChecks;
 
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub config: Config,
-    pub db_pool: DatabasePool,
+    /// Pool of the primary. Only the contexts use it, so that the code handling requests
+    /// can only write in the transactions of [`Context::begin`](crate::context::Context::begin).
+    pub(super) db_pool: DatabasePool,
+    /// Pool for read-only access outside transactions, routed to replicas when configured.
+    /// Postgres rejects writes made through it.
+    pub db_read_pool: DatabaseReadPool,
+    /// Clients of the services in [`Config::http_clients`]
+    pub http_clients: HttpClients,
+    /// Started when the server is asked to stop
+    pub shutdown: Shutdown,
+    /// Run for `GET /health/ready`
+    pub health_checks: HealthChecks,
 }
 
 impl Environment {
@@ -23,6 +38,57 @@ impl Environment {
 
     pub async fn init_with_config(config: Config) -> Result<Self, InternalError> {
         let db_pool = DatabasePool::init_pool(&config.database.url).await?;
-        Ok(Environment { config, db_pool })
+        let db_read_pool = DatabaseReadPool::init(&config.database).await?;
+        let http_clients = HttpClients::init(&config.http_clients)?;
+        Ok(Environment {
+            config,
+            db_pool,
+            db_read_pool,
+            http_clients,
+            shutdown: Shutdown::new(),
+            health_checks: HealthChecks::standard(),
+        })
+    }
+
+    /// Builds the environment without connecting to the database. Connections are
+    /// only opened when they are first used.
+    #[cfg(test)]
+    pub fn init_lazy_with_config(config: Config) -> Result<Self, InternalError> {
+        let db_pool = DatabasePool::init_lazy_pool(&config.database.url)?;
+        let db_read_pool = DatabaseReadPool::init_lazy(&config.database)?;
+        let http_clients = HttpClients::init(&config.http_clients)?;
+        Ok(Environment {
+            config,
+            db_pool,
+            db_read_pool,
+            http_clients,
+            shutdown: Shutdown::new(),
+            health_checks: HealthChecks::standard(),
+        })
+    }
+
+    /// A connection to the primary outside of any transaction, for the migrations,
+    /// bulk loads with `COPY` and the health checks
+    pub async fn db_connection(&self) -> Result<DatabaseConnection, InternalError> {
+        self.db_pool.connection().await
+    }
+
+    /// Subscribes to notifications sent on the primary, see [`DatabasePool::listen`]
+    pub async fn listen<T: DeserializeOwned>(
+        &self,
+        channels: &[&str],
+    ) -> Result<impl Stream<Item = Result<DbNotification<T>, InternalError>>, InternalError> {
+        self.db_pool.listen(channels).await
+    }
+
+    pub fn db_pool_usage(&self) -> PoolUsage {
+        self.db_pool.usage()
+    }
+
+    /// Closes the connections of all database pools. Waits for the connections in use
+    /// to be returned.
+    pub async fn close(&self) {
+        self.db_pool.close().await;
+        self.db_read_pool.close().await;
     }
 }
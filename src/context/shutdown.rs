@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Whether the server is shutting down, and how many requests it is still handling.
+/// Shared by all clones.
+#[derive(Debug, Clone)]
+pub struct Shutdown(Arc<ShutdownState>);
+
+#[derive(Debug)]
+struct ShutdownState {
+    started: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Shutdown(Arc::new(ShutdownState {
+            started: watch::channel(false).0,
+            in_flight: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+        }))
+    }
+
+    /// Starts the shutdown. Returns `false` if it had already been started.
+    pub fn start(&self) -> bool {
+        !self.0.started.send_replace(true)
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.0.started.borrow()
+    }
+
+    /// Completes once the shutdown has been started
+    pub async fn started(&self) {
+        let mut started = self.0.started.subscribe();
+        // The sender lives as long as `self`, so this can't fail
+        let _ = started.wait_for(|started| *started).await;
+    }
+
+    /// Counts the request as in flight until the guard is dropped
+    pub fn track_request(&self) -> RequestGuard {
+        self.0.in_flight.fetch_add(1, Ordering::SeqCst);
+        RequestGuard(self.clone())
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.0.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// The number of requests that have been handled
+    pub fn completed(&self) -> u64 {
+        self.0.completed.load(Ordering::SeqCst)
+    }
+}
+
+/// Returned by [`Shutdown::track_request`]
+#[derive(Debug)]
+pub struct RequestGuard(Shutdown);
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        let state = &(self.0).0;
+        state.in_flight.fetch_sub(1, Ordering::SeqCst);
+        state.completed.fetch_add(1, Ordering::SeqCst);
+    }
+}
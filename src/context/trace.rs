@@ -0,0 +1,68 @@
+use http::HeaderMap;
+use http_client::PropagatedHeaders;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// W3C trace context headers, passed on unchanged
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Longer request ids are replaced, so that they can't flood the logs
+const MAX_REQUEST_ID_LENGTH: usize = 200;
+
+/// Identifies the request being handled, in the logs and in the requests made to other
+/// services. Taken from the headers of the incoming request, with a new request id if it
+/// has none.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RequestTrace {
+    pub request_id: String,
+    pub traceparent: Option<String>,
+    pub tracestate: Option<String>,
+}
+
+impl Default for RequestTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestTrace {
+    /// A trace with a new request id, for work that doesn't come from a request
+    pub fn new() -> Self {
+        RequestTrace {
+            request_id: Uuid::new_v4().to_string(),
+            traceparent: None,
+            tracestate: None,
+        }
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let request_id = header(REQUEST_ID_HEADER)
+            .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        RequestTrace {
+            request_id,
+            traceparent: header(TRACEPARENT_HEADER),
+            tracestate: header(TRACESTATE_HEADER),
+        }
+    }
+}
+
+/// Sent to other services
+impl PropagatedHeaders for RequestTrace {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            (REQUEST_ID_HEADER, Some(self.request_id.as_str())),
+            (TRACEPARENT_HEADER, self.traceparent.as_deref()),
+            (TRACESTATE_HEADER, self.tracestate.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+    }
+}
@@ -0,0 +1,102 @@
+use std::mem;
+use std::thread;
+
+type Hook = Box<dyn FnOnce() + Send + Sync + 'static>;
+
+/// Callbacks registered inside a transaction that are run once the outcome of
+/// the outermost transaction is known
+#[derive(Default)]
+pub struct TransactionHooks {
+    on_commit: Vec<Hook>,
+    on_rollback: Vec<Hook>,
+}
+
+impl TransactionHooks {
+    pub fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.on_commit.push(Box::new(hook));
+    }
+
+    pub fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.on_rollback.push(Box::new(hook));
+    }
+
+    /// Moves all hooks from a released savepoint into this (parent) transaction
+    pub fn merge(&mut self, mut other: TransactionHooks) {
+        self.on_commit.append(&mut other.on_commit);
+        self.on_rollback.append(&mut other.on_rollback);
+    }
+
+    /// Runs the commit hooks and discards the rollback hooks
+    pub fn committed(self) {
+        for hook in self.on_commit {
+            hook();
+        }
+    }
+
+    /// Runs the rollback hooks and discards the commit hooks
+    pub fn rolled_back(self) {
+        for hook in self.on_rollback {
+            hook();
+        }
+    }
+}
+
+/// The hooks of a transaction context: of the outermost transaction, or of a savepoint
+/// whose hooks are handed to its parent when it is released.
+///
+/// An outermost transaction that is dropped without being committed or rolled back can't
+/// commit anymore, so its rollback hooks are run, unless the thread is panicking.
+/// The hooks of a savepoint that is rolled back or dropped are discarded.
+pub struct ContextHooks<'a> {
+    hooks: TransactionHooks,
+    parent: Option<&'a mut TransactionHooks>,
+}
+
+impl<'a> ContextHooks<'a> {
+    pub fn outermost() -> Self {
+        ContextHooks {
+            hooks: TransactionHooks::default(),
+            parent: None,
+        }
+    }
+
+    pub fn savepoint(parent: &'a mut ContextHooks<'_>) -> Self {
+        ContextHooks {
+            hooks: TransactionHooks::default(),
+            parent: Some(&mut parent.hooks),
+        }
+    }
+
+    pub fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_commit(hook);
+    }
+
+    pub fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_rollback(hook);
+    }
+
+    /// Runs the commit hooks, or merges them into the parent of a savepoint
+    pub fn committed(mut self) {
+        let hooks = mem::take(&mut self.hooks);
+        match self.parent.take() {
+            Some(parent) => parent.merge(hooks),
+            None => hooks.committed(),
+        }
+    }
+
+    /// Runs the rollback hooks, or discards all hooks of a savepoint
+    pub fn rolled_back(mut self) {
+        let hooks = mem::take(&mut self.hooks);
+        if self.parent.take().is_none() {
+            hooks.rolled_back();
+        }
+    }
+}
+
+impl Drop for ContextHooks<'_> {
+    fn drop(&mut self) {
+        if self.parent.is_none() && !thread::panicking() {
+            mem::take(&mut self.hooks).rolled_back();
+        }
+    }
+}
@@ -1,7 +1,17 @@
+mod advisory_lock;
+mod copy;
 mod database;
 mod migrate;
+mod notify;
+mod pinned;
+mod replica;
 mod schema;
 
+pub use advisory_lock::*;
+pub use copy::*;
 pub use database::*;
 pub use migrate::*;
+pub use notify::*;
+pub use pinned::*;
+pub use replica::*;
 pub use schema::*;
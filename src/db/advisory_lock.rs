@@ -0,0 +1,232 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use sqlx::{Connection, Executor, PgConnection};
+use tokio::runtime::Handle;
+use tracing::warn;
+
+use sql::{sql, SqlExecute};
+
+use crate::db::{DatabaseAccess, DatabaseConnection, DatabasePool, DatabaseReadAccess};
+use crate::error::InternalError;
+
+/// SQLSTATE of the error raised when `lock_timeout` expires
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+/// SQLSTATE of the error raised when `statement_timeout` expires
+const QUERY_CANCELED: &str = "57014";
+
+/// Key of a Postgres advisory lock
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct LockKey(pub i64);
+
+impl LockKey {
+    /// Derives a key from a name using 64-bit FNV-1a, so that every instance
+    /// (and every build) maps the same name to the same key
+    pub const fn from_name(name: &str) -> Self {
+        let bytes = name.as_bytes();
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+            i += 1;
+        }
+        LockKey(hash as i64)
+    }
+}
+
+impl From<i64> for LockKey {
+    fn from(key: i64) -> Self {
+        LockKey(key)
+    }
+}
+
+impl From<&str> for LockKey {
+    fn from(name: &str) -> Self {
+        LockKey::from_name(name)
+    }
+}
+
+impl Display for LockKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A session-level advisory lock. The lock is held on a connection that is
+/// dedicated to it, and it is released when this guard is dropped.
+#[derive(Debug)]
+pub struct AdvisoryLock {
+    key: LockKey,
+    conn: Option<DatabaseConnection>,
+}
+
+impl AdvisoryLock {
+    pub fn key(&self) -> LockKey {
+        self.key
+    }
+
+    /// Releases the lock, returning any error from unlocking it
+    pub async fn release(mut self) -> Result<(), InternalError> {
+        let mut conn = self.conn.take().expect("Lock connection missing");
+        unlock(&mut conn, self.key).await
+    }
+}
+
+impl Drop for AdvisoryLock {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        let key = self.key;
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(e) = unlock(&mut conn, key).await {
+                        // Closing the session releases all of its locks
+                        warn!("Could not release advisory lock {key}, closing connection: {e}");
+                        conn.close_on_drop();
+                    }
+                });
+            }
+            Err(_) => conn.close_on_drop(),
+        }
+    }
+}
+
+async fn unlock(conn: &mut DatabaseConnection, key: LockKey) -> Result<(), InternalError> {
+    let key = key.0;
+    conn.execute(sql!("SELECT pg_advisory_unlock(${key})"))
+        .await?;
+    Ok(())
+}
+
+impl DatabasePool {
+    /// Tries to take a session-level advisory lock without waiting
+    pub async fn try_advisory_lock(
+        &self,
+        key: impl Into<LockKey>,
+    ) -> Result<Option<AdvisoryLock>, InternalError> {
+        let key = key.into();
+        let mut conn = self.connection().await?;
+        if try_lock(&mut conn, key).await? {
+            Ok(Some(AdvisoryLock {
+                key,
+                conn: Some(conn),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Takes a session-level advisory lock, waiting at most `timeout` for it
+    pub async fn advisory_lock(
+        &self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> Result<AdvisoryLock, InternalError> {
+        let key = key.into();
+        let mut conn = self.connection().await?;
+        lock(&mut conn, key, timeout).await?;
+        Ok(AdvisoryLock {
+            key,
+            conn: Some(conn),
+        })
+    }
+}
+
+async fn try_lock(conn: &mut DatabaseConnection, key: LockKey) -> Result<bool, InternalError> {
+    let key = key.0;
+    let (locked,) = conn
+        .fetch_one::<(bool,)>(sql!("SELECT pg_try_advisory_lock(${key})"))
+        .await?;
+    Ok(locked)
+}
+
+/// Waits for a session-level lock in a transaction of its own, as the timeouts can only
+/// be set for a transaction. The lock outlives the transaction.
+async fn lock(
+    conn: &mut DatabaseConnection,
+    key: LockKey,
+    timeout: Duration,
+) -> Result<(), InternalError> {
+    let lock_timeout = timeout_setting(timeout);
+    let statement_timeout = timeout_setting(timeout);
+    let id = key.0;
+    let mut tx = conn.begin().await.map_err(InternalError::from)?;
+    tx.execute(sql!(
+        // language=postgresql
+        "SELECT set_config('lock_timeout', ${lock_timeout}, true),
+                set_config('statement_timeout', ${statement_timeout}, true)"
+    ))
+    .await
+    .map_err(InternalError::from)?;
+    wait_for_lock(&mut tx, sql!("SELECT pg_advisory_lock(${id})"), key).await?;
+    tx.commit().await.map_err(InternalError::from)
+}
+
+/// Waits for a transaction-level advisory lock with `lock_timeout` set to the timeout,
+/// and sets `lock_timeout` back afterwards
+pub async fn wait_for_advisory_xact_lock(
+    conn: &mut PgConnection,
+    key: LockKey,
+    timeout: Duration,
+) -> Result<(), InternalError> {
+    let lock_timeout = timeout_setting(timeout);
+    let id = key.0;
+    let previous: String = sqlx::query_scalar("SELECT current_setting('lock_timeout')")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(InternalError::from)?;
+    conn.execute(sql!(
+        "SELECT set_config('lock_timeout', ${lock_timeout}, true)"
+    ))
+    .await
+    .map_err(InternalError::from)?;
+    wait_for_lock(conn, sql!("SELECT pg_advisory_xact_lock(${id})"), key).await?;
+    conn.execute(sql!("SELECT set_config('lock_timeout', ${previous}, true)"))
+        .await
+        .map_err(InternalError::from)?;
+    Ok(())
+}
+
+/// Runs a statement that waits for the lock, turning the error of an expired timeout
+/// into one that names the lock
+async fn wait_for_lock<'q>(
+    conn: &mut PgConnection,
+    statement: impl SqlExecute<'q> + 'q,
+    key: LockKey,
+) -> Result<(), InternalError> {
+    match conn.execute(statement).await {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::Database(e))
+            if matches!(
+                e.code().as_deref(),
+                Some(LOCK_NOT_AVAILABLE | QUERY_CANCELED)
+            ) =>
+        {
+            Err(InternalError::message(format!(
+                "Timed out waiting for advisory lock {key}"
+            )))
+        }
+        Err(e) => Err(InternalError::from(e)),
+    }
+}
+
+/// The timeout as the value of a setting in milliseconds. It is at least one, as zero
+/// turns the timeout off.
+fn timeout_setting(timeout: Duration) -> String {
+    timeout.as_millis().max(1).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::LockKey;
+
+    #[test]
+    fn test_lock_key_from_name_is_stable() {
+        assert_eq!(LockKey::from_name("leader"), LockKey(-7123372470201095452));
+        assert_eq!(LockKey::from("leader"), LockKey::from_name("leader"));
+        assert_ne!(LockKey::from_name("leader"), LockKey::from_name("follower"));
+    }
+}
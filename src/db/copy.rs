@@ -0,0 +1,184 @@
+use std::ops::DerefMut;
+use std::pin::pin;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_core::stream::BoxStream;
+use futures_core::Stream;
+use futures_util::{stream, StreamExt};
+use sqlx::postgres::PgCopyIn;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use sql::encode_sql_identifier;
+
+use crate::db::DatabaseAccess;
+use crate::error::InternalError;
+
+/// Size of the chunks that rows are collected into before sending them to Postgres
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Data format used with `COPY`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CopyFormat {
+    /// CSV without a header row, with `NULL` written as an unquoted empty field
+    Csv,
+    /// Postgres binary `COPY` format
+    Binary,
+}
+
+/// Builds a `COPY` statement with the table and column names encoded as identifiers
+pub fn copy_statement(
+    table: &str,
+    columns: &[&str],
+    format: CopyFormat,
+    direction: &str,
+) -> String {
+    let columns = columns
+        .iter()
+        .map(|c| encode_sql_identifier(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let format = match format {
+        CopyFormat::Csv => "csv",
+        CopyFormat::Binary => "binary",
+    };
+    format!(
+        "COPY {} ({columns}) {direction} WITH (FORMAT {format})",
+        encode_sql_identifier(table)
+    )
+}
+
+/// Sends all data to an active `COPY ... FROM STDIN`, aborting the copy if the
+/// data stream fails
+pub async fn send_copy_data<C: DerefMut<Target = PgConnection>>(
+    mut copy: PgCopyIn<C>,
+    data: impl Stream<Item = Result<Bytes, InternalError>>,
+) -> Result<u64, InternalError> {
+    let mut data = pin!(data);
+    while let Some(chunk) = data.next().await {
+        match chunk {
+            Ok(chunk) => {
+                copy.send(chunk).await.map_err(InternalError::from)?;
+            }
+            Err(e) => {
+                copy.abort(e.to_string())
+                    .await
+                    .map_err(InternalError::from)?;
+                return Err(e);
+            }
+        }
+    }
+    copy.finish().await.map_err(InternalError::from)
+}
+
+/// A row type that can be bulk copied to and from its table as CSV
+pub trait CopyRow {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [&'static str];
+
+    /// Values of the row in the order of [`CopyRow::COLUMNS`]; `None` for `NULL`
+    fn csv_fields(&self) -> Vec<Option<String>>;
+}
+
+impl<T: CopyRow> CopyRow for &T {
+    const TABLE: &'static str = T::TABLE;
+    const COLUMNS: &'static [&'static str] = T::COLUMNS;
+
+    fn csv_fields(&self) -> Vec<Option<String>> {
+        (*self).csv_fields()
+    }
+}
+
+/// Converts a value into a CSV field for `COPY`
+pub trait ToCsvField {
+    fn to_csv_field(&self) -> Option<String>;
+}
+
+impl ToCsvField for String {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl ToCsvField for Uuid {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl ToCsvField for DateTime<Utc> {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.to_rfc3339())
+    }
+}
+
+impl ToCsvField for i32 {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl ToCsvField for i64 {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl ToCsvField for bool {
+    fn to_csv_field(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl<T: ToCsvField> ToCsvField for Option<T> {
+    fn to_csv_field(&self) -> Option<String> {
+        self.as_ref().and_then(ToCsvField::to_csv_field)
+    }
+}
+
+/// Appends the fields as one CSV line. Empty strings are quoted so that they
+/// are not read as `NULL`.
+pub fn write_csv_line(out: &mut String, fields: &[Option<String>]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        match field {
+            None => {}
+            Some(value) if value.is_empty() || value.contains([',', '"', '\n', '\r']) => {
+                out.push('"');
+                out.push_str(&value.replace('"', "\"\""));
+                out.push('"');
+            }
+            Some(value) => out.push_str(value),
+        }
+    }
+    out.push('\n');
+}
+
+/// Bulk inserts the rows into their table with `COPY`, returning the number of rows copied
+pub async fn copy_rows_in<T: CopyRow + Send>(
+    db: &mut impl DatabaseAccess,
+    rows: impl IntoIterator<Item = T, IntoIter: Send> + Send,
+) -> Result<u64, InternalError> {
+    let mut rows = rows.into_iter().peekable();
+    let chunks = std::iter::from_fn(move || {
+        rows.peek()?;
+        let mut chunk = String::with_capacity(COPY_CHUNK_SIZE);
+        while chunk.len() < COPY_CHUNK_SIZE {
+            let Some(row) = rows.next() else { break };
+            write_csv_line(&mut chunk, &row.csv_fields());
+        }
+        Some(Ok(Bytes::from(chunk)))
+    });
+    db.copy_in(T::TABLE, T::COLUMNS, CopyFormat::Csv, stream::iter(chunks))
+        .await
+}
+
+/// Streams all rows of the table out as CSV, with columns in the order of [`CopyRow::COLUMNS`]
+pub async fn copy_rows_out_csv<T: CopyRow>(
+    db: &mut impl DatabaseAccess,
+) -> Result<BoxStream<'_, Result<Bytes, InternalError>>, InternalError> {
+    db.copy_out(T::TABLE, T::COLUMNS, CopyFormat::Csv).await
+}
@@ -1,31 +1,36 @@
+use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::time::Duration;
 
+use bytes::Bytes;
 use futures_core::future::BoxFuture;
-use futures_util::FutureExt;
+use futures_core::stream::BoxStream;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use serde::Serialize;
 use sqlx::pool::PoolConnection;
-use sqlx::postgres::{PgQueryResult, PgRow};
-use sqlx::{Acquire, Execute, Executor, FromRow, PgPool, PgTransaction, Pool, Postgres};
+use sqlx::postgres::{PgConnectOptions, PgConnection, PgPoolOptions, PgQueryResult, PgRow};
+use sqlx::{Acquire, Executor, FromRow, PgPool, PgTransaction, Postgres};
 
-use crate::error::InternalError;
+use sql::{sql, SqlExecute};
 
-pub trait DatabaseAccess: Send + Sync {
-    fn execute<'e, 'q: 'e, E>(
-        &'e mut self,
-        query: E,
-    ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>>
-    where
-        E: 'q + Execute<'q, Postgres>;
+use crate::db::{copy_statement, send_copy_data, wait_for_advisory_xact_lock, CopyFormat, LockKey};
+use crate::error::InternalError;
 
+/// Read access to the database. Contexts hand this out for code that must not
+/// modify data; see [`DatabaseAccess`] for the writable counterpart.
+pub trait DatabaseReadAccess: Send + Sync {
     fn fetch_rows<'e, 'q: 'e, E>(
         &'e mut self,
         query: E,
     ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>>
     where
-        E: 'q + Execute<'q, Postgres>;
+        E: 'q + SqlExecute<'q>;
 
-    fn fetch_all<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow>>(
+    fn fetch_all<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow> + 'static>(
         &'e mut self,
-        query: impl Execute<'q, Postgres> + 'q,
+        query: impl SqlExecute<'q> + 'q,
     ) -> BoxFuture<'e, Result<Vec<T>, InternalError>> {
         Box::pin(async move {
             self.fetch_rows(query)
@@ -36,9 +41,9 @@ pub trait DatabaseAccess: Send + Sync {
         })
     }
 
-    fn fetch_one<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow>>(
+    fn fetch_one<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow> + 'static>(
         &'e mut self,
-        query: impl Execute<'q, Postgres> + 'q,
+        query: impl SqlExecute<'q> + 'q,
     ) -> BoxFuture<'e, Result<T, InternalError>> {
         Box::pin(async move {
             let mut rows = self.fetch_all(query).await?;
@@ -54,9 +59,9 @@ pub trait DatabaseAccess: Send + Sync {
         })
     }
 
-    fn fetch_optional<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow>>(
+    fn fetch_optional<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow> + 'static>(
         &'e mut self,
-        query: impl Execute<'q, Postgres> + 'q,
+        query: impl SqlExecute<'q> + 'q,
     ) -> BoxFuture<'e, Result<Option<T>, InternalError>> {
         Box::pin(async move {
             let mut rows = self.fetch_all(query).await?;
@@ -71,36 +76,163 @@ pub trait DatabaseAccess: Send + Sync {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct DatabasePool(PgPool);
+/// Full read-write access to the database
+pub trait DatabaseAccess: DatabaseReadAccess {
+    fn execute<'e, 'q: 'e, E>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>>
+    where
+        E: 'q + SqlExecute<'q>;
 
-impl Deref for DatabasePool {
-    type Target = PgPool;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// Bulk loads data into the given columns of the table with `COPY ... FROM STDIN`,
+    /// returning the number of rows copied. The copy is aborted if the data stream fails.
+    fn copy_in<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+        data: impl Stream<Item = Result<Bytes, InternalError>> + Send + 'e,
+    ) -> BoxFuture<'e, Result<u64, InternalError>>;
+
+    /// Streams the given columns of the table out with `COPY ... TO STDOUT`
+    fn copy_out<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+    ) -> BoxFuture<'e, Result<BoxStream<'e, Result<Bytes, InternalError>>, InternalError>>;
+
+    /// Sends a notification with a JSON payload to the listeners of the channel.
+    /// Inside a transaction, the notification is only delivered when the transaction
+    /// commits, and it is discarded if the transaction is rolled back.
+    fn notify<'e>(
+        &'e mut self,
+        channel: &'e str,
+        payload: &impl Serialize,
+    ) -> BoxFuture<'e, Result<(), InternalError>> {
+        let payload = serde_json::to_string(payload).map_err(InternalError::from);
+        Box::pin(async move {
+            let payload = payload?;
+            self.execute(sql!(
+                // language=postgresql
+                "SELECT pg_notify(${channel}, ${payload})"
+            ))
+            .await?;
+            Ok(())
+        })
+    }
+
+    /// Tries to take a transaction-level advisory lock without waiting. The lock is held
+    /// until the surrounding transaction ends (outside a transaction it is released as
+    /// soon as the statement completes).
+    fn try_advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+    ) -> BoxFuture<'_, Result<bool, InternalError>> {
+        let key = key.into().0;
+        Box::pin(async move {
+            let (locked,) = self
+                .fetch_one::<(bool,)>(sql!("SELECT pg_try_advisory_xact_lock(${key})"))
+                .await?;
+            Ok(locked)
+        })
     }
+
+    /// Takes a transaction-level advisory lock, waiting at most `timeout` for it. When it
+    /// times out, the transaction is aborted like on any other error, so wait in a
+    /// savepoint to carry on without the lock.
+    fn advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), InternalError>>;
 }
 
+#[derive(Debug, Clone)]
+pub struct DatabasePool(PgPool);
+
 impl DatabasePool {
     pub async fn init_pool(url: &str) -> Result<Self, InternalError> {
         let pool = PgPool::connect(url).await.map_err(InternalError::from)?;
         Ok(DatabasePool(pool))
     }
-}
 
-impl DatabaseAccess for DatabasePool {
-    fn execute<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
-        &'e mut self,
-        query: E,
-    ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>> {
-        Box::pin(async move {
-            let mut conn = self.0.acquire().await.map_err(InternalError::from)?;
-            conn.execute(query).await.map_err(InternalError::from)
-        })
-        .boxed()
+    /// Creates a pool that only connects once a connection is first needed
+    #[cfg(test)]
+    pub fn init_lazy_pool(url: &str) -> Result<Self, InternalError> {
+        let pool = PgPool::connect_lazy(url).map_err(InternalError::from)?;
+        Ok(DatabasePool(pool))
     }
 
-    fn fetch_rows<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
+    /// Creates a pool whose connections have `default_transaction_read_only` turned on,
+    /// so that Postgres rejects any write made through them
+    pub async fn init_read_only_pool(url: &str) -> Result<Self, InternalError> {
+        let pool = PgPool::connect_with(read_only_options(url)?)
+            .await
+            .map_err(InternalError::from)?;
+        Ok(DatabasePool(pool))
+    }
+
+    /// Creates a read-only pool that only connects once a connection is first needed,
+    /// so that an unreachable server does not prevent the application from starting
+    pub fn init_lazy_read_only_pool(
+        url: &str,
+        acquire_timeout: Duration,
+    ) -> Result<Self, InternalError> {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(acquire_timeout)
+            .connect_lazy_with(read_only_options(url)?);
+        Ok(DatabasePool(pool))
+    }
+
+    /// The sqlx pool, for the other modules of `db`. It is not handed out any further, as
+    /// writes could be made through it outside of a transaction.
+    pub(super) fn pg_pool(&self) -> &PgPool {
+        &self.0
+    }
+
+    /// Closes the pool, waiting for the connections in use to be returned
+    pub async fn close(&self) {
+        self.0.close().await;
+    }
+
+    /// Takes a connection out of the pool, for work that doesn't run in the transaction
+    /// of a context, such as migrations or bulk loads with `COPY`
+    pub async fn connection(&self) -> Result<DatabaseConnection, InternalError> {
+        let conn = self.0.acquire().await.map_err(InternalError::from)?;
+        Ok(DatabaseConnection::new(conn))
+    }
+
+    pub fn usage(&self) -> PoolUsage {
+        let size = self.0.size();
+        let idle = self.0.num_idle() as u32;
+        PoolUsage {
+            size,
+            idle,
+            in_use: size.saturating_sub(idle),
+            max: self.0.options().get_max_connections(),
+        }
+    }
+}
+
+/// Connections of a pool, as reported by the health checks
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct PoolUsage {
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max: u32,
+}
+
+fn read_only_options(url: &str) -> Result<PgConnectOptions, InternalError> {
+    Ok(PgConnectOptions::from_str(url)
+        .map_err(InternalError::from)?
+        .options([("default_transaction_read_only", "on")]))
+}
+
+impl DatabaseReadAccess for DatabasePool {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
         &'e mut self,
         query: E,
     ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
@@ -114,6 +246,12 @@ impl DatabaseAccess for DatabasePool {
 #[derive(Debug)]
 pub struct DatabaseConnection(PoolConnection<Postgres>);
 
+impl DatabaseConnection {
+    pub fn new(conn: PoolConnection<Postgres>) -> Self {
+        DatabaseConnection(conn)
+    }
+}
+
 impl Deref for DatabaseConnection {
     type Target = PoolConnection<Postgres>;
     fn deref(&self) -> &Self::Target {
@@ -127,19 +265,54 @@ impl DerefMut for DatabaseConnection {
     }
 }
 
+impl DatabaseReadAccess for DatabaseConnection {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        Box::pin(async move { self.0.fetch_all(query).await.map_err(InternalError::from) })
+    }
+}
+
 impl DatabaseAccess for DatabaseConnection {
-    fn execute<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
+    fn execute<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
         &'e mut self,
         query: E,
     ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>> {
         Box::pin(async move { self.0.execute(query).await.map_err(InternalError::from) })
     }
 
-    fn fetch_rows<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
+    fn copy_in<'e>(
         &'e mut self,
-        query: E,
-    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
-        Box::pin(async move { self.0.fetch_all(query).await.map_err(InternalError::from) })
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+        data: impl Stream<Item = Result<Bytes, InternalError>> + Send + 'e,
+    ) -> BoxFuture<'e, Result<u64, InternalError>> {
+        Box::pin(copy_in_conn(&mut self.0, table, columns, format, data))
+    }
+
+    fn copy_out<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+    ) -> BoxFuture<'e, Result<BoxStream<'e, Result<Bytes, InternalError>>, InternalError>> {
+        Box::pin(copy_out_conn(&mut self.0, table, columns, format))
+    }
+
+    // Outside a transaction, the lock is released again right away
+    fn advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), InternalError>> {
+        let key = key.into();
+        Box::pin(async move {
+            let mut tx = self.0.begin().await.map_err(InternalError::from)?;
+            wait_for_advisory_xact_lock(&mut tx, key, timeout).await?;
+            tx.commit().await.map_err(InternalError::from)
+        })
     }
 }
 
@@ -147,29 +320,141 @@ pub struct TransactionalConnection<'a> {
     tx: PgTransaction<'a>,
 }
 
+impl DatabaseReadAccess for TransactionalConnection<'_> {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        Box::pin(async move { self.tx.fetch_all(query).await.map_err(InternalError::from) })
+    }
+}
+
 impl DatabaseAccess for TransactionalConnection<'_> {
-    fn execute<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
+    fn execute<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
         &'e mut self,
         query: E,
     ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>> {
         Box::pin(async move { self.tx.execute(query).await.map_err(InternalError::from) })
     }
 
-    fn fetch_rows<'e, 'q: 'e, E: 'q + Execute<'q, Postgres>>(
+    fn copy_in<'e>(
         &'e mut self,
-        query: E,
-    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
-        Box::pin(async move { self.tx.fetch_all(query).await.map_err(InternalError::from) })
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+        data: impl Stream<Item = Result<Bytes, InternalError>> + Send + 'e,
+    ) -> BoxFuture<'e, Result<u64, InternalError>> {
+        Box::pin(copy_in_conn(&mut self.tx, table, columns, format, data))
+    }
+
+    fn copy_out<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+    ) -> BoxFuture<'e, Result<BoxStream<'e, Result<Bytes, InternalError>>, InternalError>> {
+        Box::pin(copy_out_conn(&mut self.tx, table, columns, format))
+    }
+
+    fn advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), InternalError>> {
+        Box::pin(wait_for_advisory_xact_lock(
+            &mut self.tx,
+            key.into(),
+            timeout,
+        ))
     }
 }
 
+async fn copy_in_conn(
+    conn: &mut PgConnection,
+    table: &str,
+    columns: &[&str],
+    format: CopyFormat,
+    data: impl Stream<Item = Result<Bytes, InternalError>> + Send,
+) -> Result<u64, InternalError> {
+    let statement = copy_statement(table, columns, format, "FROM STDIN");
+    let copy = conn
+        .copy_in_raw(&statement)
+        .await
+        .map_err(InternalError::from)?;
+    send_copy_data(copy, data).await
+}
+
+async fn copy_out_conn<'c>(
+    conn: &'c mut PgConnection,
+    table: &str,
+    columns: &[&str],
+    format: CopyFormat,
+) -> Result<BoxStream<'c, Result<Bytes, InternalError>>, InternalError> {
+    let statement = copy_statement(table, columns, format, "TO STDOUT");
+    let rows = conn
+        .copy_out_raw(&statement)
+        .await
+        .map_err(InternalError::from)?;
+    Ok(rows.map(|r| r.map_err(InternalError::from)).boxed())
+}
+
+/// Postgres configuration parameters that are set for the duration of each transaction,
+/// for example `app.user_id` for row-level security policies
+pub type SessionSettings = BTreeMap<String, String>;
+
 impl TransactionalConnection<'_> {
-    pub async fn begin_from_pool(pool: &Pool<Postgres>) -> Result<Self, InternalError> {
-        let tx = pool.begin().await.map_err(InternalError::from)?;
-        Ok(TransactionalConnection { tx })
+    pub async fn begin_from_pool(
+        pool: &DatabasePool,
+        settings: &SessionSettings,
+    ) -> Result<Self, InternalError> {
+        let tx = pool.0.begin().await.map_err(InternalError::from)?;
+        let mut conn = TransactionalConnection { tx };
+        conn.apply_session_settings(settings).await?;
+        Ok(conn)
+    }
+
+    /// Begins a transaction on a connection that is held by the caller. The connection
+    /// may come from a read-only pool, so the transaction is explicitly made writable.
+    pub async fn begin_from_connection<'c>(
+        conn: &'c mut DatabaseConnection,
+        settings: &SessionSettings,
+    ) -> Result<TransactionalConnection<'c>, InternalError> {
+        let mut tx = conn.0.begin().await.map_err(InternalError::from)?;
+        tx.execute("SET TRANSACTION READ WRITE")
+            .await
+            .map_err(InternalError::from)?;
+        let mut conn = TransactionalConnection { tx };
+        conn.apply_session_settings(settings).await?;
+        Ok(conn)
+    }
+
+    /// Settings are local to the transaction, so savepoints within it see them as well
+    async fn apply_session_settings(
+        &mut self,
+        settings: &SessionSettings,
+    ) -> Result<(), InternalError> {
+        for (name, value) in settings {
+            self.set_session_setting(name, value).await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the parameter until the end of the transaction. The value is bound as a
+    /// parameter.
+    pub async fn set_session_setting(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), InternalError> {
+        self.execute(sql!(
+            // language=postgresql
+            "SELECT set_config(${name}, ${value}, true)"
+        ))
+        .await?;
+        Ok(())
     }
 
-    pub async fn begin(&mut self) -> Result<TransactionalConnection, InternalError> {
+    pub async fn begin(&mut self) -> Result<TransactionalConnection<'_>, InternalError> {
         let tx = self.tx.begin().await.map_err(InternalError::from)?;
         Ok(TransactionalConnection { tx })
     }
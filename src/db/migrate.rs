@@ -2,15 +2,21 @@ use sqlx::migrate::Migrator;
 use tracing::info;
 
 use crate::context::Environment;
+use crate::db::DatabaseConnection;
 use crate::error::InternalError;
 
-static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 pub async fn run_db_migrations(env: &Environment) -> Result<(), InternalError> {
-    let pool = env.db_pool.clone();
+    run_migrations(&mut env.db_connection().await?).await
+}
 
+pub async fn run_migrations(conn: &mut DatabaseConnection) -> Result<(), InternalError> {
     info!("Running DB migrations");
-    MIGRATOR.run(&*pool).await.map_err(InternalError::from)?;
+    MIGRATOR
+        .run(&mut **conn)
+        .await
+        .map_err(InternalError::from)?;
     info!("DB migrations complete");
     Ok(())
 }
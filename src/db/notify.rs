@@ -0,0 +1,95 @@
+use std::pin::pin;
+use std::time::Duration;
+
+use async_stream::stream;
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use sqlx::postgres::{PgListener, PgNotification};
+use sqlx::PgPool;
+use tracing::{debug, warn};
+
+use crate::db::DatabasePool;
+use crate::error::InternalError;
+
+/// How long to wait before subscribing again after the listener connection fails
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A notification received from one of the listened channels
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DbNotification<T> {
+    pub channel: String,
+    pub payload: T,
+}
+
+impl DatabasePool {
+    /// Subscribes to the given channels and returns a stream of their notifications,
+    /// with payloads decoded from JSON. The listener reconnects and subscribes again
+    /// if its connection is lost; notifications sent while disconnected are missed.
+    /// The stream ends when the pool is closed. Until it is polled to its end, the
+    /// listener holds a connection, which closing the pool waits for.
+    pub async fn listen<T: DeserializeOwned>(
+        &self,
+        channels: &[&str],
+    ) -> Result<impl Stream<Item = Result<DbNotification<T>, InternalError>>, InternalError> {
+        let pool = self.pg_pool().clone();
+        let channels = channels.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let mut listener = Some(subscribe(&pool, &channels).await?);
+        Ok(stream! {
+            let mut closed = pin!(pool.close_event());
+            loop {
+                let current = match listener.as_mut() {
+                    Some(current) => current,
+                    None => {
+                        let subscribed = tokio::select! {
+                            () = &mut closed => break,
+                            subscribed = subscribe(&pool, &channels) => subscribed,
+                        };
+                        match subscribed {
+                            Ok(subscribed) => listener.insert(subscribed),
+                            Err(e) => {
+                                warn!("Could not subscribe to {channels:?}: {e}");
+                                tokio::select! {
+                                    () = &mut closed => break,
+                                    () = tokio::time::sleep(RESUBSCRIBE_INTERVAL) => continue,
+                                }
+                            }
+                        }
+                    }
+                };
+                // The listener keeps waiting on its connection after the pool is closed
+                let received = tokio::select! {
+                    () = &mut closed => break,
+                    received = current.try_recv() => received,
+                };
+                match received {
+                    Ok(Some(notification)) => yield decode(notification),
+                    Ok(None) => debug!("Listener connection lost, reconnected to {channels:?}"),
+                    Err(e) => {
+                        warn!("Listener for {channels:?} failed, subscribing again: {e}");
+                        listener = None;
+                    }
+                }
+            }
+        })
+    }
+}
+
+async fn subscribe(pool: &PgPool, channels: &[String]) -> Result<PgListener, InternalError> {
+    let mut listener = PgListener::connect_with(pool)
+        .await
+        .map_err(InternalError::from)?;
+    listener
+        .listen_all(channels.iter().map(String::as_str))
+        .await
+        .map_err(InternalError::from)?;
+    Ok(listener)
+}
+
+fn decode<T: DeserializeOwned>(
+    notification: PgNotification,
+) -> Result<DbNotification<T>, InternalError> {
+    Ok(DbNotification {
+        channel: notification.channel().to_string(),
+        payload: serde_json::from_str(notification.payload())?,
+    })
+}
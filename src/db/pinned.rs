@@ -0,0 +1,84 @@
+use futures_core::future::BoxFuture;
+use sql::SqlExecute;
+use sqlx::postgres::PgRow;
+use sqlx::Executor;
+use tokio::runtime::Handle;
+use tracing::warn;
+
+use crate::db::{DatabaseConnection, DatabaseReadAccess, DatabaseReadPool};
+use crate::error::InternalError;
+
+/// Read access for a root context. Queries go through the shared read pool, unless
+/// pinning is enabled: then a single connection to the primary is acquired on first
+/// use and reused for every later query, so that session state is kept between them.
+///
+/// A pinned connection is returned to the pool when this is dropped, after its
+/// session settings have been reset.
+#[derive(Debug)]
+pub struct PinnableConnection {
+    read_pool: DatabaseReadPool,
+    pin: bool,
+    conn: Option<DatabaseConnection>,
+}
+
+impl PinnableConnection {
+    pub fn new(read_pool: DatabaseReadPool, pin: bool) -> Self {
+        PinnableConnection {
+            read_pool,
+            pin,
+            conn: None,
+        }
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pin
+    }
+
+    pub fn use_primary(&mut self) {
+        self.read_pool.use_primary();
+    }
+
+    /// Returns the pinned connection, acquiring it if this is the first use
+    pub async fn connection(&mut self) -> Result<&mut DatabaseConnection, InternalError> {
+        if self.conn.is_none() {
+            let conn = self.read_pool.acquire_primary().await?;
+            self.conn = Some(DatabaseConnection::new(conn));
+        }
+        Ok(self.conn.as_mut().unwrap())
+    }
+}
+
+impl DatabaseReadAccess for PinnableConnection {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        Box::pin(async move {
+            if self.pin {
+                self.connection().await?.fetch_rows(query).await
+            } else {
+                self.read_pool.fetch_rows(query).await
+            }
+        })
+    }
+}
+
+impl Drop for PinnableConnection {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        // Settings changed with SET would otherwise leak to the next user of the connection
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(e) = conn.execute("RESET ALL").await {
+                        warn!("Could not reset pinned connection, closing it: {e}");
+                        conn.close_on_drop();
+                    }
+                });
+            }
+            Err(_) => conn.close_on_drop(),
+        }
+    }
+}
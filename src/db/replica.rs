@@ -0,0 +1,177 @@
+use sql::SqlExecute;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_core::future::BoxFuture;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::PgRow;
+use sqlx::{Executor, Postgres};
+use tracing::warn;
+
+use crate::context::{DatabaseSettings, ReplicaSelection};
+use crate::db::{DatabasePool, DatabaseReadAccess};
+use crate::error::InternalError;
+
+/// How long to wait for a replica connection before falling back to the primary
+const REPLICA_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long to wait for a connection to the primary from a lazily connected pool,
+/// the same as the sqlx default
+#[cfg(test)]
+const PRIMARY_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long an unreachable replica is skipped before it is tried again
+const REPLICA_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Read-only pool that routes queries to the configured streaming replicas, falling
+/// back to a read-only connection to the primary when no replica is available.
+#[derive(Debug, Clone)]
+pub struct DatabaseReadPool {
+    primary: DatabasePool,
+    replicas: Arc<Replicas>,
+    use_primary: bool,
+}
+
+#[derive(Debug)]
+struct Replicas {
+    selection: ReplicaSelection,
+    pools: Vec<Replica>,
+    next: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Replica {
+    pool: DatabasePool,
+    unhealthy_since: Mutex<Option<Instant>>,
+}
+
+impl DatabaseReadPool {
+    pub async fn init(settings: &DatabaseSettings) -> Result<Self, InternalError> {
+        let primary = DatabasePool::init_read_only_pool(&settings.url).await?;
+        Self::with_primary(primary, settings)
+    }
+
+    /// Like [`DatabaseReadPool::init`], but does not connect to the primary until a
+    /// connection is first needed
+    #[cfg(test)]
+    pub fn init_lazy(settings: &DatabaseSettings) -> Result<Self, InternalError> {
+        let primary =
+            DatabasePool::init_lazy_read_only_pool(&settings.url, PRIMARY_ACQUIRE_TIMEOUT)?;
+        Self::with_primary(primary, settings)
+    }
+
+    fn with_primary(
+        primary: DatabasePool,
+        settings: &DatabaseSettings,
+    ) -> Result<Self, InternalError> {
+        let pools = settings
+            .replica_urls
+            .iter()
+            .map(|url| {
+                Ok(Replica {
+                    pool: DatabasePool::init_lazy_read_only_pool(url, REPLICA_ACQUIRE_TIMEOUT)?,
+                    unhealthy_since: Mutex::new(None),
+                })
+            })
+            .collect::<Result<Vec<_>, InternalError>>()?;
+        Ok(DatabaseReadPool {
+            primary,
+            replicas: Arc::new(Replicas {
+                selection: settings.replica_selection,
+                pools,
+                next: AtomicUsize::new(0),
+            }),
+            use_primary: false,
+        })
+    }
+
+    /// Routes all subsequent reads through this pool to the primary. Use this when the
+    /// reads must see writes that were just committed (read-your-writes consistency).
+    pub fn use_primary(&mut self) {
+        self.use_primary = true;
+    }
+
+    pub async fn acquire(&self) -> Result<PoolConnection<Postgres>, InternalError> {
+        if !self.use_primary {
+            if let Some(replica) = self.replicas.select() {
+                match replica.pool.pg_pool().acquire().await {
+                    Ok(conn) => {
+                        replica.set_healthy(true);
+                        return Ok(conn);
+                    }
+                    Err(e) => {
+                        warn!("Read replica unavailable, falling back to primary: {e}");
+                        replica.set_healthy(false);
+                    }
+                }
+            }
+        }
+        self.acquire_primary().await
+    }
+
+    pub async fn acquire_primary(&self) -> Result<PoolConnection<Postgres>, InternalError> {
+        self.primary
+            .pg_pool()
+            .acquire()
+            .await
+            .map_err(InternalError::from)
+    }
+
+    /// Closes the pool of the primary and of all replicas
+    pub async fn close(&self) {
+        self.primary.close().await;
+        for replica in &self.replicas.pools {
+            replica.pool.close().await;
+        }
+    }
+}
+
+impl Replicas {
+    fn select(&self) -> Option<&Replica> {
+        let available = self
+            .pools
+            .iter()
+            .filter(|r| r.is_available())
+            .collect::<Vec<_>>();
+        if available.is_empty() {
+            return None;
+        }
+        match self.selection {
+            ReplicaSelection::RoundRobin => {
+                let next = self.next.fetch_add(1, Ordering::Relaxed);
+                Some(available[next % available.len()])
+            }
+            ReplicaSelection::LeastConnections => {
+                available.into_iter().min_by_key(|r| r.connections_in_use())
+            }
+        }
+    }
+}
+
+impl Replica {
+    fn is_available(&self) -> bool {
+        match *self.unhealthy_since.lock().unwrap() {
+            Some(since) => since.elapsed() >= REPLICA_RETRY_INTERVAL,
+            None => true,
+        }
+    }
+
+    fn set_healthy(&self, healthy: bool) {
+        *self.unhealthy_since.lock().unwrap() = if healthy { None } else { Some(Instant::now()) };
+    }
+
+    fn connections_in_use(&self) -> usize {
+        self.pool.usage().in_use as usize
+    }
+}
+
+impl DatabaseReadAccess for DatabaseReadPool {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        Box::pin(async move {
+            let mut conn = self.acquire().await?;
+            conn.fetch_all(query).await.map_err(InternalError::from)
+        })
+    }
+}
@@ -2,10 +2,26 @@ use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, FromRow, Eq, PartialEq)]
+use crate::db::{CopyRow, ToCsvField};
+
+#[derive(Debug, Clone, FromRow, Eq, PartialEq)]
 pub struct DbThing {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
 }
+
+impl CopyRow for DbThing {
+    const TABLE: &'static str = "things";
+    const COLUMNS: &'static [&'static str] = &["id", "name", "description", "created_at"];
+
+    fn csv_fields(&self) -> Vec<Option<String>> {
+        vec![
+            self.id.to_csv_field(),
+            self.name.to_csv_field(),
+            self.description.to_csv_field(),
+            self.created_at.to_csv_field(),
+        ]
+    }
+}
@@ -41,3 +41,15 @@ impl From<io::Error> for InternalError {
         InternalError(format!("{}", e))
     }
 }
+
+impl From<http_client::HttpClientError> for InternalError {
+    fn from(e: http_client::HttpClientError) -> Self {
+        InternalError(format!("{}", e))
+    }
+}
+
+impl From<serde_json::Error> for InternalError {
+    fn from(e: serde_json::Error) -> Self {
+        InternalError(format!("{}", e))
+    }
+}
@@ -0,0 +1,5 @@
+mod checks;
+mod health_check;
+
+pub use checks::*;
+pub use health_check::*;
@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+
+use futures_core::future::BoxFuture;
+use serde_json::json;
+
+use crate::context::{Config, Environment, RequestTrace};
+use crate::db::MIGRATOR;
+use crate::error::InternalError;
+use crate::health::{HealthCheck, HealthCheckResult};
+
+/// Runs a query on the primary database
+#[derive(Debug)]
+pub struct DatabaseCheck;
+
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn check<'a>(&'a self, env: &'a Environment) -> BoxFuture<'a, HealthCheckResult> {
+        Box::pin(async move {
+            let mut conn = env.db_connection().await?;
+            sqlx::query("SELECT 1")
+                .execute(&mut **conn)
+                .await
+                .map_err(InternalError::from)?;
+            Ok(None)
+        })
+    }
+}
+
+/// Fails while migrations of the server have not been applied to the database
+#[derive(Debug)]
+pub struct MigrationsCheck;
+
+impl HealthCheck for MigrationsCheck {
+    fn name(&self) -> &str {
+        "migrations"
+    }
+
+    fn check<'a>(&'a self, env: &'a Environment) -> BoxFuture<'a, HealthCheckResult> {
+        Box::pin(async move {
+            let mut conn = env.db_connection().await?;
+            let applied: BTreeSet<i64> =
+                sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                    .fetch_all(&mut **conn)
+                    .await
+                    .map_err(InternalError::from)?
+                    .into_iter()
+                    .collect();
+            let pending = MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+                .filter(|m| !applied.contains(&m.version))
+                .map(|m| m.version)
+                .collect::<Vec<_>>();
+            if !pending.is_empty() {
+                return Err(InternalError::message(format!(
+                    "Migrations not applied: {pending:?}"
+                )));
+            }
+            Ok(Some(json!({ "applied": applied.len() })))
+        })
+    }
+}
+
+/// Reports the usage of the pool of the primary database
+#[derive(Debug)]
+pub struct DatabasePoolCheck;
+
+impl HealthCheck for DatabasePoolCheck {
+    fn name(&self) -> &str {
+        "database_pool"
+    }
+
+    fn check<'a>(&'a self, env: &'a Environment) -> BoxFuture<'a, HealthCheckResult> {
+        Box::pin(async move { Ok(Some(serde_json::to_value(env.db_pool_usage())?)) })
+    }
+}
+
+/// Fails while a service the server depends on doesn't answer on the `health_path` of
+/// its client
+#[derive(Debug)]
+pub struct HttpServiceCheck {
+    name: String,
+    client: String,
+    path: String,
+}
+
+impl HttpServiceCheck {
+    /// A check for each client in the configuration that has a `health_path`
+    pub fn configured(config: &Config) -> Vec<Self> {
+        config
+            .http_clients
+            .iter()
+            .filter_map(|(client, settings)| {
+                Some(HttpServiceCheck {
+                    name: format!("http_{client}"),
+                    client: client.clone(),
+                    path: settings.health_path.clone()?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl HealthCheck for HttpServiceCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check<'a>(&'a self, env: &'a Environment) -> BoxFuture<'a, HealthCheckResult> {
+        Box::pin(async move {
+            let client = env.http_clients.get(&self.client)?;
+            client.probe(&RequestTrace::new(), &self.path).await?;
+            Ok(None)
+        })
+    }
+}
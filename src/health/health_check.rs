@@ -0,0 +1,87 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_core::future::BoxFuture;
+use futures_util::future::join_all;
+use serde_json::Value;
+
+use crate::context::Environment;
+use crate::error::InternalError;
+use crate::health::{DatabaseCheck, DatabasePoolCheck, MigrationsCheck};
+
+/// A check of a dependency of the server, run for `GET /health/ready`. The server is
+/// only ready when all checks succeed.
+///
+/// A check returns details to report, such as the usage of a resource. Checks that take
+/// longer than `server.health_check_timeout_ms` fail.
+pub trait HealthCheck: Debug + Send + Sync {
+    /// Identifies the check in the report
+    fn name(&self) -> &str;
+
+    fn check<'a>(&'a self, env: &'a Environment) -> BoxFuture<'a, HealthCheckResult>;
+}
+
+pub type HealthCheckResult = Result<Option<Value>, InternalError>;
+
+/// The result of a [`HealthCheck`]
+#[derive(Debug)]
+pub struct HealthCheckOutcome {
+    pub name: String,
+    pub duration: Duration,
+    pub result: HealthCheckResult,
+}
+
+/// The checks of the readiness of the server. Subsystems add their own with
+/// [`HealthChecks::register`] before the server is started.
+#[derive(Debug, Clone, Default)]
+pub struct HealthChecks(Arc<Vec<Arc<dyn HealthCheck>>>);
+
+impl HealthChecks {
+    /// The checks of the database
+    pub fn standard() -> Self {
+        HealthChecks(Arc::new(vec![
+            Arc::new(DatabaseCheck),
+            Arc::new(MigrationsCheck),
+            Arc::new(DatabasePoolCheck),
+        ]))
+    }
+
+    /// Adds the check. Fails if a check with the same name is registered already, as
+    /// the report has one entry per name.
+    pub fn register(&mut self, check: impl HealthCheck + 'static) -> Result<(), InternalError> {
+        if self
+            .0
+            .iter()
+            .any(|registered| registered.name() == check.name())
+        {
+            return Err(InternalError::message(format!(
+                "Health check {} is registered already",
+                check.name()
+            )));
+        }
+        Arc::make_mut(&mut self.0).push(Arc::new(check));
+        Ok(())
+    }
+
+    /// Runs all checks concurrently, each with the timeout of the configuration
+    pub async fn run(&self, env: &Environment) -> Vec<HealthCheckOutcome> {
+        let timeout = Duration::from_millis(env.config.server.health_check_timeout_ms);
+        join_all(self.0.iter().map(|check| async move {
+            let start = Instant::now();
+            let result = match tokio::time::timeout(timeout, check.check(env)).await {
+                Ok(result) => result,
+                Err(_) => Err(InternalError::message(format!(
+                    "Timed out after {} ms",
+                    timeout.as_millis()
+                ))),
+            };
+            HealthCheckOutcome {
+                name: check.name().to_string(),
+                duration: start.elapsed(),
+                result,
+            }
+        }))
+        .await
+    }
+}
@@ -0,0 +1,11 @@
+pub mod app;
+pub mod context;
+pub mod db;
+pub mod error;
+pub mod health;
+pub mod logging;
+pub mod service;
+
+mod macros;
+#[cfg(test)]
+mod tests;
@@ -1,37 +1,33 @@
-use tracing::{error, info};
-
-use crate::app::start_server;
-use crate::context::Environment;
-use crate::db::run_db_migrations;
-use crate::error::InternalError;
-use crate::logging::configure_logging;
+use std::process::ExitCode;
 
-mod app;
-mod context;
-mod db;
-mod error;
-mod logging;
-mod service;
+use tracing::{error, info};
 
-mod macros;
-#[cfg(test)]
-mod tests;
+use rust_server::app::{start_server, ShutdownSummary};
+use rust_server::context::Environment;
+use rust_server::db::run_db_migrations;
+use rust_server::error::InternalError;
+use rust_server::health::HttpServiceCheck;
+use rust_server::logging::configure_logging;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     configure_logging();
     match start_main().await {
-        Ok(_) => (),
+        Ok(summary) if summary.is_clean() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
         Err(e) => {
-            error!("Error starting server: {e}")
+            error!("Error running server: {e}");
+            ExitCode::FAILURE
         }
     }
 }
 
-async fn start_main() -> Result<(), InternalError> {
-    let env = Environment::init().await?;
+async fn start_main() -> Result<ShutdownSummary, InternalError> {
+    let mut env = Environment::init().await?;
+    for check in HttpServiceCheck::configured(&env.config) {
+        env.health_checks.register(check)?;
+    }
     info!("Initialized environment {}", env.config.environment_name);
     run_db_migrations(&env).await?;
-    start_server(env).await?;
-    Ok(())
+    start_server(env).await
 }
@@ -10,7 +10,7 @@ pub async fn delete_thing(
     ctx: &mut (impl Context + Transactional),
     thing_id: Uuid,
 ) -> Result<(), InternalError> {
-    ctx.db()
+    ctx.db_mut()
         .execute(sql!(
             // language=postgresql
             "DELETE FROM things WHERE id=${thing_id}"
@@ -1,6 +1,33 @@
+mod advisory_lock_test;
+mod api_client_test;
+mod copy_test;
 mod db_test;
+mod factory;
+mod factory_test;
+mod health_test;
+mod http_client_test;
+mod http_test;
+mod local_postgres;
 mod macros;
+mod mock_db;
+mod mock_test;
+mod notify_test;
+mod openapi_test;
+mod pinned_connection_test;
+mod query_count;
+mod query_count_test;
+mod replica_test;
+mod session_settings_test;
+mod shutdown_test;
+mod stub_server;
+mod test_app;
 mod test_env;
 mod thing_test;
 
+pub use factory::*;
+pub use local_postgres::*;
+pub use mock_db::*;
+pub use query_count::*;
+pub use stub_server::*;
+pub use test_app::*;
 pub use test_env::*;
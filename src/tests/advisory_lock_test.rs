@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use tokio::test;
+
+use sql::sql;
+
+use crate::context::{Context, Transactional};
+use crate::db::{DatabaseAccess, DatabaseReadAccess, LockKey};
+use crate::tests::TestEnvironment;
+
+#[test]
+pub async fn test_session_lock_is_released_on_drop() {
+    let env = TestEnvironment::init().await;
+    let ctx = env.ctx().await;
+
+    let lock = ctx.try_advisory_lock("job").await.unwrap();
+    assert!(lock.is_some());
+    assert!(ctx.try_advisory_lock("job").await.unwrap().is_none());
+    assert!(ctx.try_advisory_lock("other-job").await.unwrap().is_some());
+    drop(lock);
+
+    // The lock is released in the background
+    let lock = ctx
+        .advisory_lock("job", Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(lock.key(), LockKey::from_name("job"));
+}
+
+#[test]
+pub async fn test_session_lock_times_out() {
+    let env = TestEnvironment::init().await;
+    let ctx = env.ctx().await;
+
+    let lock = ctx.try_advisory_lock(1).await.unwrap().unwrap();
+    let error = ctx
+        .advisory_lock(1, Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Error: Timed out waiting for advisory lock 1"
+    );
+    lock.release().await.unwrap();
+    assert!(ctx.try_advisory_lock(1).await.unwrap().is_some());
+}
+
+#[test]
+pub async fn test_transaction_lock_is_held_until_commit() {
+    let env = TestEnvironment::init().await;
+    let mut ctx_1 = env.ctx().await;
+    let mut ctx_2 = env.ctx().await;
+
+    let mut tx_1 = ctx_1.begin().await.unwrap();
+    assert!(tx_1.db_mut().try_advisory_xact_lock("job").await.unwrap());
+
+    let mut tx_2 = ctx_2.begin().await.unwrap();
+    assert!(!tx_2.db_mut().try_advisory_xact_lock("job").await.unwrap());
+    // A timeout aborts the transaction, so it is waited for in a savepoint
+    let mut savepoint = tx_2.begin().await.unwrap();
+    let error = savepoint
+        .db_mut()
+        .advisory_xact_lock("job", Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Error: Timed out waiting for advisory lock {}",
+            LockKey::from_name("job")
+        )
+    );
+    savepoint.rollback().await.unwrap();
+
+    tx_1.commit().await.unwrap();
+    tx_2.db_mut()
+        .advisory_xact_lock("job", Duration::from_secs(5))
+        .await
+        .unwrap();
+    let (lock_timeout,) = tx_2
+        .db_mut()
+        .fetch_one::<(String,)>(sql!("SELECT current_setting('lock_timeout')"))
+        .await
+        .unwrap();
+    assert_eq!(lock_timeout, "0");
+    tx_2.commit().await.unwrap();
+}
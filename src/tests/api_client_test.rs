@@ -0,0 +1,82 @@
+use api_client::{ApiClient, ApiErrorCode, ApiThingData, ClientError, HealthStatus};
+use http::StatusCode;
+use tokio::test;
+use uuid::Uuid;
+
+use crate::app::create_app;
+use crate::tests::{StubServer, TestEnvironment};
+
+async fn start_app(env: &TestEnvironment) -> (StubServer, ApiClient) {
+    let server = StubServer::start(create_app(env.env.clone())).await;
+    let client = ApiClient::new(&server.url);
+    (server, client)
+}
+
+#[test]
+pub async fn test_client_get_root() {
+    let env = TestEnvironment::init().await;
+    let (_server, client) = start_app(&env).await;
+
+    let root = client.get_root().await.unwrap();
+    assert_eq!(root.status, "ok");
+    assert_eq!(root.env, "Test");
+}
+
+#[test]
+pub async fn test_client_health() {
+    let env = TestEnvironment::init().await;
+    let (_server, client) = start_app(&env).await;
+
+    assert_eq!(client.get_live().await.unwrap().status, HealthStatus::Ok);
+    let ready = client.get_ready().await.unwrap();
+    assert_eq!(ready.status, HealthStatus::Ok);
+    assert!(ready.checks["database"].healthy);
+
+    // Not being ready is an answer, not an error
+    env.env.shutdown.start();
+    let ready = client.get_ready().await.unwrap();
+    assert_eq!(ready.status, HealthStatus::ShuttingDown);
+}
+
+#[test]
+pub async fn test_client_thing_lifecycle() {
+    let env = TestEnvironment::init().await;
+    let (_server, client) = start_app(&env).await;
+    let data = ApiThingData {
+        name: "thingy".to_string(),
+        description: Some("from the client".to_string()),
+    };
+
+    let created = client.create_thing(&data).await.unwrap();
+    assert_eq!(created.name, data.name);
+    assert_eq!(created.description, data.description);
+    assert_eq!(client.get_thing(created.id).await.unwrap(), created);
+
+    client.delete_thing(created.id).await.unwrap();
+    assert_eq!(client.find_thing(created.id).await.unwrap(), None);
+}
+
+#[test]
+pub async fn test_client_maps_api_errors() {
+    let env = TestEnvironment::init().await;
+    let (_server, client) = start_app(&env).await;
+
+    let error = client.get_thing(Uuid::from_u128(1)).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::NOT_FOUND,
+            code: ApiErrorCode::NotFound,
+        }
+    ));
+}
+
+#[test]
+pub async fn test_client_request_errors() {
+    let env = TestEnvironment::init().await;
+    let (server, client) = start_app(&env).await;
+    drop(server);
+
+    let error = client.get_root().await.unwrap_err();
+    assert!(matches!(error, ClientError::Request(_)), "{error}");
+}
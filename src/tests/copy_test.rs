@@ -0,0 +1,144 @@
+use bytes::Bytes;
+use chrono::{SubsecRound, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use tokio::test;
+use uuid::{uuid, Uuid};
+
+use sql::sql;
+
+use crate::context::{Context, Transactional};
+use crate::db::{
+    copy_rows_in, copy_rows_out_csv, CopyFormat, DatabaseAccess, DatabaseReadAccess, DbThing,
+};
+use crate::error::InternalError;
+use crate::service::find_thing;
+use crate::tests::TestEnvironment;
+
+#[test]
+pub async fn test_copy_things_in_and_out_as_csv() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let created_at = Utc::now().trunc_subsecs(6);
+    let things = vec![
+        DbThing {
+            id: uuid!("019524da-be46-7553-94c9-490815a51432"),
+            name: "plain".to_string(),
+            description: None,
+            created_at,
+        },
+        DbThing {
+            id: uuid!("019524da-be46-7553-94c9-490815a51433"),
+            name: "with \"quotes\", commas\nand newlines".to_string(),
+            description: Some(String::new()),
+            created_at,
+        },
+    ];
+
+    let mut tx = ctx.begin().await.unwrap();
+    let copied = copy_rows_in(tx.db_mut(), &things).await.unwrap();
+    assert_eq!(copied, 2);
+    tx.commit().await.unwrap();
+
+    for thing in &things {
+        assert_eq!(
+            find_thing(&mut ctx, thing.id).await.unwrap().as_ref(),
+            Some(thing)
+        );
+    }
+
+    let mut tx = ctx.begin().await.unwrap();
+    let csv = copy_rows_out_csv::<DbThing>(tx.db_mut())
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .concat();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with("019524da-be46-7553-94c9-490815a51432,plain,,"));
+    assert!(csv.contains(
+        "019524da-be46-7553-94c9-490815a51433,\"with \"\"quotes\"\", commas\nand newlines\",\"\","
+    ));
+}
+
+#[test]
+pub async fn test_copy_binary_between_tables() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let mut conn = env.env.db_connection().await.unwrap();
+    let columns = ["id", "name", "description", "created_at"];
+    conn.execute(sql!("CREATE TABLE things_copy (LIKE things)"))
+        .await
+        .unwrap();
+    copy_rows_in(
+        &mut conn,
+        (0..1000).map(|i| DbThing {
+            id: Uuid::from_u128(i),
+            name: format!("thing {i}"),
+            description: Some(format!("description {i}")),
+            created_at: Utc::now().trunc_subsecs(6),
+        }),
+    )
+    .await
+    .unwrap();
+
+    let data = env
+        .env
+        .db_connection()
+        .await
+        .unwrap()
+        .copy_out("things", &columns, CopyFormat::Binary)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    let copied = conn
+        .copy_in(
+            "things_copy",
+            &columns,
+            CopyFormat::Binary,
+            stream::iter(data),
+        )
+        .await
+        .unwrap();
+    assert_eq!(copied, 1000);
+
+    let (count,) = ctx
+        .db()
+        .fetch_one::<(i64,)>(sql!(
+            // language=postgresql
+            "SELECT COUNT(*) FROM things_copy c JOIN things t USING (id, name, description, created_at)"
+        ))
+        .await
+        .unwrap();
+    assert_eq!(count, 1000);
+}
+
+#[test]
+pub async fn test_copy_is_aborted_when_data_fails() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let mut conn = env.env.db_connection().await.unwrap();
+    let data = stream::iter(vec![
+        Ok(Bytes::from(
+            "019524da-be46-7553-94c9-490815a51432,name,,2025-02-20T10:00:00Z\n",
+        )),
+        Err(InternalError::message("Source failed".to_string())),
+    ]);
+    let res = conn
+        .copy_in(
+            "things",
+            &["id", "name", "description", "created_at"],
+            CopyFormat::Csv,
+            data,
+        )
+        .await;
+    assert!(res.is_err());
+
+    let (count,) = ctx
+        .db()
+        .fetch_one::<(i64,)>(sql!("SELECT COUNT(*) FROM things"))
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
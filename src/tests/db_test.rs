@@ -1,11 +1,12 @@
 use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
 
 use tokio::test;
 
 use sql::sql;
 
 use crate::context::{Context, Transactional};
-use crate::db::DatabaseAccess;
+use crate::db::{DatabaseAccess, DatabaseReadAccess};
 use crate::set;
 use crate::tests::TestEnvironment;
 
@@ -73,19 +74,162 @@ pub async fn test_nested_transaction_is_rolled_back_but_main_is_committed() {
     assert_eq!(foo_values(&mut ctx).await, set![1, 3]);
 }
 
+#[test]
+pub async fn test_commit_hooks_are_run_after_commit() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    add_value(&mut tx, 1).await;
+    tx.on_commit(events.push("commit"));
+    tx.on_rollback(events.push("rollback"));
+    assert_eq!(events.get(), Vec::<&str>::new());
+    tx.commit().await.unwrap();
+
+    assert_eq!(events.get(), vec!["commit"]);
+}
+
+#[test]
+pub async fn test_rollback_hooks_are_run_on_rollback_and_drop() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_commit(events.push("commit-1"));
+    tx.on_rollback(events.push("rollback-1"));
+    tx.rollback().await.unwrap();
+    assert_eq!(events.get(), vec!["rollback-1"]);
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_commit(events.push("commit-2"));
+    tx.on_rollback(events.push("rollback-2"));
+    drop(tx);
+    assert_eq!(events.get(), vec!["rollback-1", "rollback-2"]);
+}
+
+#[test]
+pub async fn test_released_savepoint_hooks_are_run_with_outer_transaction() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_commit(events.push("outer"));
+    let mut nested = tx.begin().await.unwrap();
+    nested.on_commit(events.push("nested"));
+    nested.on_rollback(events.push("nested-rollback"));
+    nested.commit().await.unwrap();
+    assert_eq!(events.get(), Vec::<&str>::new());
+    tx.commit().await.unwrap();
+
+    assert_eq!(events.get(), vec!["outer", "nested"]);
+}
+
+#[test]
+pub async fn test_rolled_back_savepoint_hooks_are_discarded() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_commit(events.push("outer"));
+    let mut nested = tx.begin().await.unwrap();
+    nested.on_commit(events.push("nested"));
+    nested.on_rollback(events.push("nested-rollback"));
+    nested.rollback().await.unwrap();
+    assert_eq!(events.get(), Vec::<&str>::new());
+    let mut dropped = tx.begin().await.unwrap();
+    dropped.on_rollback(events.push("dropped-rollback"));
+    drop(dropped);
+    assert_eq!(events.get(), Vec::<&str>::new());
+    tx.commit().await.unwrap();
+
+    assert_eq!(events.get(), vec!["outer"]);
+}
+
+#[test]
+pub async fn test_released_savepoint_rollback_hooks_are_run_with_outer_rollback() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_rollback(events.push("outer-rollback"));
+    let mut released = tx.begin().await.unwrap();
+    released.on_commit(events.push("released"));
+    released.on_rollback(events.push("released-rollback"));
+    released.commit().await.unwrap();
+    let mut rolled_back = tx.begin().await.unwrap();
+    rolled_back.on_rollback(events.push("rolled-back-rollback"));
+    rolled_back.rollback().await.unwrap();
+    assert_eq!(events.get(), Vec::<&str>::new());
+    tx.rollback().await.unwrap();
+
+    assert_eq!(events.get(), vec!["outer-rollback", "released-rollback"]);
+}
+
+#[test]
+pub async fn test_rollback_hooks_are_not_run_while_panicking() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let events = Events::default();
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.on_rollback(events.push("rollback"));
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        let _tx = tx;
+        panic!("failed while in a transaction");
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(events.get(), Vec::<&str>::new());
+}
+
+#[test]
+pub async fn test_root_context_reads_are_read_only() {
+    let env = init_fixtures().await;
+    let mut ctx = env.ctx().await;
+    let res = ctx
+        .db()
+        .fetch_all::<(i32,)>(sql!(
+            // language=postgresql
+            "INSERT INTO foo (value) VALUES (1) RETURNING value"
+        ))
+        .await;
+    assert!(res.is_err());
+    assert_eq!(foo_count(&mut ctx).await, 0);
+}
+
+#[derive(Default, Clone)]
+struct Events(Arc<Mutex<Vec<&'static str>>>);
+
+impl Events {
+    fn push(&self, event: &'static str) -> impl FnOnce() + Send + Sync + 'static {
+        let events = self.clone();
+        move || events.0.lock().unwrap().push(event)
+    }
+
+    fn get(&self) -> Vec<&'static str> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
 async fn init_fixtures() -> TestEnvironment {
     let env = TestEnvironment::init().await;
-    env.ctx()
-        .await
-        .db()
+    let mut ctx = env.ctx().await;
+    let mut tx = ctx.begin().await.unwrap();
+    tx.db_mut()
         .execute(sql!("CREATE TABLE foo (value INTEGER)"))
         .await
         .unwrap();
+    tx.commit().await.unwrap();
     env
 }
 
-async fn add_value(ctx: &mut impl Context, value: i32) {
-    ctx.db()
+async fn add_value(ctx: &mut (impl Context + Transactional), value: i32) {
+    ctx.db_mut()
         .execute(sql!(
             // language=postgresql
             "INSERT INTO foo (value) VALUES (${value})"
@@ -0,0 +1,31 @@
+mod thing;
+
+pub use thing::*;
+
+/// Deterministic random number generator (SplitMix64) for test data, so that a
+/// factory seeded with the same value always generates the same data
+#[derive(Debug, Clone)]
+pub struct FactoryRng(u64);
+
+impl FactoryRng {
+    pub fn seeded(seed: u64) -> Self {
+        FactoryRng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[(self.next_u64() % items.len() as u64) as usize]
+    }
+
+    /// Returns `true` with the given probability in percent
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.next_u64() % 100 < percent
+    }
+}
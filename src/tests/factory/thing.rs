@@ -0,0 +1,171 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use sql::sql;
+
+use crate::context::{Context, Transactional};
+use crate::db::{DatabaseReadAccess, DbThing};
+use crate::error::InternalError;
+use crate::service::ThingData;
+use crate::tests::FactoryRng;
+
+const WORDS: &[&str] = &[
+    "red", "green", "blue", "small", "large", "round", "square", "shiny", "old", "new",
+];
+
+/// A thing that has not been inserted yet, with all fields that can be set
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NewThing {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+enum CreatedAt {
+    Now,
+    Fixed(DateTime<Utc>),
+    Every {
+        start: DateTime<Utc>,
+        step: TimeDelta,
+    },
+}
+
+/// Generates valid things. Names are numbered in sequence (`thing-1`, `thing-2`, ...),
+/// descriptions are random but determined by the seed, and things are created now.
+/// Each field can be overridden for all things the factory generates.
+#[derive(Debug, Clone)]
+pub struct ThingFactory {
+    rng: FactoryRng,
+    sequence: usize,
+    name: Option<String>,
+    description: Option<Option<String>>,
+    created_at: CreatedAt,
+}
+
+impl Default for ThingFactory {
+    fn default() -> Self {
+        Self::seeded(0)
+    }
+}
+
+impl ThingFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        ThingFactory {
+            rng: FactoryRng::seeded(seed),
+            sequence: 0,
+            name: None,
+            description: None,
+            created_at: CreatedAt::Now,
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn description(mut self, description: Option<&str>) -> Self {
+        self.description = Some(description.map(str::to_string));
+        self
+    }
+
+    /// Backdates (or postdates) all things to the given time
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = CreatedAt::Fixed(created_at);
+        self
+    }
+
+    /// Creates the first thing at `start` and each following one `step` later
+    pub fn created_every(mut self, start: DateTime<Utc>, step: TimeDelta) -> Self {
+        self.created_at = CreatedAt::Every { start, step };
+        self
+    }
+
+    pub fn build(&mut self) -> NewThing {
+        let index = self.sequence;
+        self.sequence += 1;
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => format!("thing-{}", index + 1),
+        };
+        let description = match &self.description {
+            Some(description) => description.clone(),
+            None => self.random_description(),
+        };
+        let created_at = match &self.created_at {
+            CreatedAt::Now => Utc::now(),
+            CreatedAt::Fixed(created_at) => *created_at,
+            CreatedAt::Every { start, step } => *start + *step * index as i32,
+        };
+        NewThing {
+            name,
+            description,
+            created_at,
+        }
+    }
+
+    /// Builds the data of a thing, as it is passed to the service to add it
+    pub fn build_data(&mut self) -> ThingData {
+        let NewThing {
+            name, description, ..
+        } = self.build();
+        ThingData { name, description }
+    }
+
+    pub fn build_many(&mut self, count: usize) -> Vec<NewThing> {
+        (0..count).map(|_| self.build()).collect()
+    }
+
+    /// Builds and inserts `count` things, see [`insert_things`]
+    pub async fn insert(
+        &mut self,
+        ctx: &mut (impl Context + Transactional),
+        count: usize,
+    ) -> Result<Vec<DbThing>, InternalError> {
+        let things = self.build_many(count);
+        insert_things(ctx, &things).await
+    }
+
+    fn random_description(&mut self) -> Option<String> {
+        if self.rng.chance(25) {
+            return None;
+        }
+        let words = (0..3).map(|_| *self.rng.pick(WORDS)).collect::<Vec<_>>();
+        Some(words.join(" "))
+    }
+}
+
+/// Inserts the things with a single statement and returns them in the same order.
+/// Ids are generated with `uuid_generate_v7_with_timestamp`, so that they sort in
+/// the order of `created_at` just like the ids of things created at that time would.
+pub async fn insert_things(
+    ctx: &mut (impl Context + Transactional),
+    things: &[NewThing],
+) -> Result<Vec<DbThing>, InternalError> {
+    let names = things.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+    let descriptions = things
+        .iter()
+        .map(|t| t.description.clone())
+        .collect::<Vec<_>>();
+    let created_ats = things.iter().map(|t| t.created_at).collect::<Vec<_>>();
+    ctx.db_mut()
+        .fetch_all(sql!(
+            // language=postgresql
+            "WITH input AS MATERIALIZED (
+                 SELECT uuid_generate_v7_with_timestamp(created_at) AS id,
+                        name, description, created_at, ord
+                 FROM UNNEST(${names}::TEXT[], ${descriptions}::TEXT[], ${created_ats}::TIMESTAMPTZ[])
+                      WITH ORDINALITY AS t(name, description, created_at, ord)
+             ), inserted AS (
+                 INSERT INTO things (id, name, description, created_at)
+                 SELECT id, name, description, created_at FROM input
+                 RETURNING *
+             )
+             SELECT inserted.* FROM inserted JOIN input USING (id) ORDER BY input.ord"
+        ))
+        .await
+}
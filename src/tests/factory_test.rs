@@ -0,0 +1,85 @@
+use chrono::{SubsecRound, TimeDelta, TimeZone, Utc};
+use tokio::test;
+
+use crate::context::{Context, Transactional};
+use crate::service::find_thing;
+use crate::tests::{insert_things, TestEnvironment, ThingFactory};
+
+#[test]
+pub async fn test_seeded_factories_generate_the_same_things() {
+    let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let things = ThingFactory::seeded(42)
+        .created_at(created_at)
+        .build_many(10);
+    assert_eq!(
+        things,
+        ThingFactory::seeded(42)
+            .created_at(created_at)
+            .build_many(10)
+    );
+    assert_ne!(
+        things,
+        ThingFactory::seeded(43)
+            .created_at(created_at)
+            .build_many(10)
+    );
+    assert_eq!(things[0].name, "thing-1");
+    assert_eq!(things[9].name, "thing-10");
+}
+
+#[test]
+pub async fn test_factory_fields_can_be_overridden() {
+    let thing = ThingFactory::new().name("custom").description(None).build();
+    assert_eq!(thing.name, "custom");
+    assert_eq!(thing.description, None);
+}
+
+#[test]
+pub async fn test_factory_inserts_things_in_bulk() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let mut factory = ThingFactory::new().description(Some("bulk"));
+
+    let mut tx = ctx.begin().await.unwrap();
+    let things = factory.insert(&mut tx, 3).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let names = things.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["thing-1", "thing-2", "thing-3"]);
+    for thing in &things {
+        assert_eq!(
+            find_thing(&mut ctx, thing.id).await.unwrap().as_ref(),
+            Some(thing)
+        );
+    }
+}
+
+#[test]
+pub async fn test_backdated_things_have_ids_in_time_order() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let now = Utc::now().trunc_subsecs(3);
+    // Newest first, so that the insertion order differs from the time order
+    let new_things = ThingFactory::new()
+        .created_every(now, TimeDelta::days(-1))
+        .build_many(3);
+
+    let mut tx = ctx.begin().await.unwrap();
+    let mut things = insert_things(&mut tx, &new_things).await.unwrap();
+    tx.commit().await.unwrap();
+
+    for (thing, new_thing) in things.iter().zip(&new_things) {
+        assert_eq!(thing.created_at, new_thing.created_at);
+        let (secs, nanos) = thing.id.get_timestamp().unwrap().to_unix();
+        assert_eq!(
+            Utc.timestamp_opt(secs as i64, nanos).unwrap(),
+            thing.created_at
+        );
+    }
+    things.sort_by_key(|t| t.id);
+    let created_ats = things.iter().map(|t| t.created_at).collect::<Vec<_>>();
+    assert_eq!(
+        created_ats,
+        vec![now - TimeDelta::days(2), now - TimeDelta::days(1), now]
+    );
+}
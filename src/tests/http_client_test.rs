@@ -0,0 +1,281 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use config::Config as ConfigCrate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::test;
+
+use http_client::{HttpClient, HttpClientSettings};
+use uri::format_uri;
+
+use crate::context::{Config, Environment, RequestTrace, RootContext};
+use crate::tests::{test_config_with_database_url, StubServer, EXTERNAL_DATABASE_URL};
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct Item {
+    id: u32,
+    name: String,
+}
+
+fn settings(server: &StubServer) -> HttpClientSettings {
+    HttpClientSettings {
+        base_url: format!("{}/api/", server.url),
+        timeout_ms: 200,
+        connect_timeout_ms: 200,
+        max_retries: 2,
+        retry_backoff_ms: 1,
+        health_path: None,
+    }
+}
+
+fn client(server: &StubServer) -> HttpClient {
+    HttpClient::new("stub", &settings(server)).unwrap()
+}
+
+/// A context that doesn't connect to the database, with a fixed request trace
+fn ctx() -> RootContext {
+    let config = test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap();
+    let env = Environment::init_lazy_with_config(config).unwrap();
+    let trace = RequestTrace {
+        request_id: "request-1".to_string(),
+        traceparent: Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string()),
+        tracestate: None,
+    };
+    RootContext::with_trace(env, trace)
+}
+
+/// Answers with the status until it has been called `failures` times, then with an item
+fn failing_handler(failures: usize, status: StatusCode) -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler = move || {
+        let calls = calls.clone();
+        async move {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                Err(status)
+            } else {
+                Ok(Json(json!({ "id": 1, "name": "one" })))
+            }
+        }
+    };
+    Router::new().route("/api/items/{id}", get(handler.clone()).post(handler))
+}
+
+#[test]
+pub async fn test_get_json_with_trace_headers() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async { Json(json!({ "id": 1, "name": "one" })) }),
+    ))
+    .await;
+    let ctx = ctx();
+    let id = 1;
+
+    let item: Item = client(&server)
+        .get(&ctx, &format_uri!("/items/{id}"))
+        .await
+        .unwrap();
+    assert_eq!(
+        item,
+        Item {
+            id: 1,
+            name: "one".to_string()
+        }
+    );
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].uri, "/api/items/1");
+    assert_eq!(requests[0].headers["accept"], "application/json");
+    assert_eq!(requests[0].headers["x-request-id"], "request-1");
+    assert_eq!(
+        requests[0].headers["traceparent"],
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+    );
+    assert!(requests[0].headers.get("tracestate").is_none());
+}
+
+#[test]
+pub async fn test_post_json_body() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items",
+        post(|Json(item): Json<Value>| async move { (StatusCode::CREATED, Json(item)) }),
+    ))
+    .await;
+    let item = Item {
+        id: 2,
+        name: "two".to_string(),
+    };
+
+    let created: Item = client(&server).post(&ctx(), "/items", &item).await.unwrap();
+    assert_eq!(created, item);
+    let requests = server.requests();
+    assert_eq!(requests[0].method, Method::POST);
+    assert_eq!(requests[0].headers["content-type"], "application/json");
+    assert_eq!(requests[0].body, r#"{"id":2,"name":"two"}"#);
+}
+
+#[test]
+pub async fn test_empty_response_is_null() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        axum::routing::delete(|| async { StatusCode::NO_CONTENT }),
+    ))
+    .await;
+
+    let () = client(&server).delete(&ctx(), "/items/1").await.unwrap();
+}
+
+#[test]
+pub async fn test_idempotent_requests_are_retried() {
+    let server = StubServer::start(failing_handler(2, StatusCode::SERVICE_UNAVAILABLE)).await;
+
+    let item: Item = client(&server).get(&ctx(), "/items/1").await.unwrap();
+    assert_eq!(item.id, 1);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+pub async fn test_retries_are_limited() {
+    let server = StubServer::start(failing_handler(3, StatusCode::BAD_GATEWAY)).await;
+
+    let error = client(&server)
+        .get::<Item>(&ctx(), "/items/1")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("GET {}/api/items/1 returned 502 Bad Gateway: ", server.url)
+    );
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+pub async fn test_non_idempotent_requests_are_not_retried() {
+    let server = StubServer::start(failing_handler(1, StatusCode::SERVICE_UNAVAILABLE)).await;
+
+    let error = client(&server)
+        .post::<_, Item>(&ctx(), "/items/1", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("returned 503 Service Unavailable"));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+pub async fn test_client_errors_are_not_retried() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async { (StatusCode::BAD_REQUEST, Json(json!({ "error": "invalid" }))) }),
+    ))
+    .await;
+
+    let error = client(&server)
+        .get::<Item>(&ctx(), "/items/1")
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .ends_with(r#"returned 400 Bad Request: {"error":"invalid"}"#));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+pub async fn test_timeouts_are_retried() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Json(json!({ "id": 1, "name": "one" }))
+        }),
+    ))
+    .await;
+
+    let error = client(&server)
+        .get::<Item>(&ctx(), "/items/1")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("failed"), "{error}");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+pub async fn test_get_optional() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async { Json(json!({ "id": 1, "name": "one" })) }),
+    ))
+    .await;
+    let client = client(&server);
+
+    let item = client
+        .get_optional::<Item>(&ctx(), "/items/1")
+        .await
+        .unwrap();
+    assert_eq!(item.map(|item| item.id), Some(1));
+    let missing = client
+        .get_optional::<Item>(&ctx(), "/things/1")
+        .await
+        .unwrap();
+    assert_eq!(missing, None);
+}
+
+#[test]
+pub async fn test_invalid_response_body() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async { Json(json!({ "id": "one" })) }),
+    ))
+    .await;
+
+    let error = client(&server)
+        .get::<Item>(&ctx(), "/items/1")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().starts_with(&format!(
+        "Invalid response to GET {}/api/items/1",
+        server.url
+    )));
+}
+
+#[test]
+pub async fn test_clients_are_configured_by_name() {
+    let server = StubServer::start(Router::new().route(
+        "/api/items/{id}",
+        get(|| async { Json(json!({ "id": 1, "name": "one" })) }),
+    ))
+    .await;
+    let config = Config::build(
+        ConfigCrate::builder()
+            .set_default("environment_name", "Test")
+            .unwrap()
+            .set_default("server.port", "6100")
+            .unwrap()
+            .set_default("database.url", EXTERNAL_DATABASE_URL)
+            .unwrap()
+            .set_default("http_clients.items.base_url", format!("{}/api", server.url))
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.http_clients["items"].timeout_ms, 10_000);
+    let env = Environment::init_lazy_with_config(config).unwrap();
+    let ctx = RootContext::new(env.clone());
+
+    let item: Item = env
+        .http_clients
+        .get("items")
+        .unwrap()
+        .get(&ctx, "/items/1")
+        .await
+        .unwrap();
+    assert_eq!(item.id, 1);
+    assert_eq!(
+        env.http_clients.get("other").unwrap_err().to_string(),
+        "No HTTP client configured for other"
+    );
+}
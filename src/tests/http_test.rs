@@ -0,0 +1,133 @@
+use assert_json::{assert_json, validators};
+use axum::body::Body;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use http::{Request, StatusCode};
+use serde_json::{json, Value};
+use tokio::test;
+
+use crate::app::add_layers;
+use crate::context::{Environment, RequestTrace};
+use crate::tests::{
+    test_config_with_database_url, TestApp, TestEnvironment, EXTERNAL_DATABASE_URL,
+};
+
+#[test]
+pub async fn test_get_root() {
+    let env = TestEnvironment::init().await;
+    let app = TestApp::new(&env);
+
+    let res = app.get("/").await;
+    res.assert_status(StatusCode::OK);
+    assert_json!(res.json(), { "status": "ok", "env": "Test" });
+}
+
+#[test]
+pub async fn test_post_and_get_thing() {
+    let env = TestEnvironment::init().await;
+    let app = TestApp::new(&env);
+
+    let res = app
+        .post_json("/things", &json!({ "name": "thingy", "description": null }))
+        .await;
+    res.assert_status(StatusCode::OK);
+    let created = res.json();
+    assert_json!(created.clone(), {
+        "id": validators::any(),
+        "name": "thingy",
+        "description": null,
+        "created_at": validators::any(),
+    });
+
+    let id = created["id"].as_str().unwrap();
+    let res = app.get(&format!("/things/{id}")).await;
+    res.assert_status(StatusCode::OK);
+    assert_eq!(res.json(), created);
+}
+
+#[test]
+pub async fn test_delete_thing() {
+    let env = TestEnvironment::init().await;
+    let app = TestApp::new(&env);
+    let res = app.post_json("/things", &json!({ "name": "thingy" })).await;
+    let id = res.json()["id"].as_str().unwrap().to_string();
+
+    app.delete(&format!("/things/{id}"))
+        .await
+        .assert_status(StatusCode::OK);
+    let res = app.get(&format!("/things/{id}")).await;
+    res.assert_status(StatusCode::NOT_FOUND);
+    assert_json!(res.json(), { "error": "not_found" });
+}
+
+#[test]
+pub async fn test_invalid_path_param_is_rejected() {
+    let env = TestEnvironment::init().await;
+    let app = TestApp::new(&env);
+
+    let res = app.get("/things/not-a-uuid").await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(
+        res.headers.get(http::header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    assert_json!(res.json(), { "error": "invalid_path_param" });
+}
+
+#[test]
+pub async fn test_panic_is_turned_into_error_response() {
+    let config = test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap();
+    let env = Environment::init_lazy_with_config(config).unwrap();
+    let routes = Router::new().route("/panic", get(panicking_handler));
+    let app = TestApp::from_router(add_layers(routes, env));
+
+    let res = app.get("/panic").await;
+    res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    assert_json!(res.json(), { "error": { "kind": "panic" } });
+}
+
+async fn panicking_handler() -> &'static str {
+    panic!("Handler panicked")
+}
+
+#[test]
+pub async fn test_request_trace_is_taken_from_headers() {
+    let config = test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap();
+    let env = Environment::init_lazy_with_config(config).unwrap();
+    let routes = Router::new().route("/trace", get(trace_handler));
+    let app = TestApp::from_router(add_layers(routes, env));
+
+    let request = Request::get("/trace")
+        .header("x-request-id", "request-1")
+        .header(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        )
+        .body(Body::empty())
+        .unwrap();
+    let res = app.request(request).await;
+    res.assert_status(StatusCode::OK);
+    assert_eq!(res.headers.get("x-request-id").unwrap(), "request-1");
+    assert_json!(res.json(), {
+        "request_id": "request-1",
+        "traceparent": "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        "tracestate": null,
+    });
+
+    let res = app.get("/trace").await;
+    let request_id = res.headers.get("x-request-id").unwrap().to_str().unwrap();
+    assert_eq!(request_id.len(), 36);
+    assert_json!(res.json(), {
+        "request_id": request_id,
+        "traceparent": null,
+        "tracestate": null,
+    });
+}
+
+async fn trace_handler(Extension(trace): Extension<RequestTrace>) -> Json<Value> {
+    Json(json!({
+        "request_id": trace.request_id,
+        "traceparent": trace.traceparent,
+        "tracestate": trace.tracestate,
+    }))
+}
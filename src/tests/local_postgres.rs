@@ -0,0 +1,157 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::net::TcpListener;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::LazyLock;
+
+use crate::error::InternalError;
+use crate::tests::{EXTERNAL_DATABASE_URL, TEST_POSTGRES_VAR};
+
+/// Directory of the Postgres binaries, if they are not on the `PATH`
+const PG_BIN_DIR_VAR: &str = "PG_BIN_DIR";
+/// Runs Postgres when the tests run as root, which Postgres refuses
+const PG_USER: &str = "postgres";
+
+static LOCAL_POSTGRES: LazyLock<Result<LocalPostgres, String>> =
+    LazyLock::new(|| LocalPostgres::start().map_err(|e| e.to_string()));
+
+/// A throwaway Postgres cluster that is created with `initdb` in a temporary directory
+/// and started with `pg_ctl` on a free port. The `pgcrypto` extension must be installed
+/// along with the binaries. Postgres refuses to run as root, so when the tests run as
+/// root, it is run as the `postgres` user with `su`.
+///
+/// The cluster is shared by all tests of the process. It is stopped and its directory
+/// removed when the process exits.
+pub struct LocalPostgres {
+    port: u16,
+}
+
+impl LocalPostgres {
+    /// Returns the cluster of this process, starting it on first use
+    pub fn shared() -> Result<&'static LocalPostgres, InternalError> {
+        LOCAL_POSTGRES
+            .as_ref()
+            .map_err(|e| InternalError::message(format!("Could not start local Postgres: {e}")))
+    }
+
+    pub fn url(&self) -> String {
+        format!("postgresql://postgres@localhost:{}/postgres", self.port)
+    }
+
+    fn start() -> Result<Self, InternalError> {
+        let dir = std::env::temp_dir().join(format!("rust-server-test-pg-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        // The directory is owned by the user the tests run as
+        let as_root = fs::metadata(&dir)?.uid() == 0;
+        Self::start_in(&dir, as_root)
+            .map_err(|e| {
+                if as_root {
+                    InternalError::message(format!(
+                        "Running Postgres as the {PG_USER} user failed, as the tests run as \
+                         root: {e}. Run the tests as another user, or leave \
+                         {TEST_POSTGRES_VAR} unset to use the database at \
+                         {EXTERNAL_DATABASE_URL}"
+                    ))
+                } else {
+                    e
+                }
+            })
+            .inspect_err(|_| {
+                // Nothing is running yet that would need the directory
+                let _ = fs::remove_dir_all(&dir);
+            })
+    }
+
+    fn start_in(dir: &Path, as_root: bool) -> Result<Self, InternalError> {
+        if as_root {
+            run(Command::new("chown").arg(PG_USER).arg(dir))?;
+        }
+        let data = dir.join("data");
+        run(pg_command("initdb", as_root)
+            .arg("-D")
+            .arg(&data)
+            .args(["-U", "postgres", "--auth=trust"])
+            .args(["-E", "UTF8", "--no-sync"]))?;
+
+        let port = free_port()?;
+        let options = format!(
+            "-p {port} -k {} -c listen_addresses=localhost -c fsync=off -c max_connections=200",
+            dir.display()
+        );
+        run(pg_command("pg_ctl", as_root)
+            .arg("-D")
+            .arg(&data)
+            .arg("-l")
+            .arg(dir.join("postgres.log"))
+            .args(["-o", &options, "-w", "start"]))?;
+        stop_on_exit(&data, dir, as_root)?;
+        Ok(LocalPostgres { port })
+    }
+}
+
+fn pg_bin(name: &str) -> PathBuf {
+    match std::env::var_os(PG_BIN_DIR_VAR) {
+        Some(dir) => PathBuf::from(dir).join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Runs the Postgres binary as [`PG_USER`] when the tests run as root
+fn pg_command(name: &str, as_root: bool) -> Command {
+    if !as_root {
+        return Command::new(pg_bin(name));
+    }
+    let mut command = Command::new("su");
+    command
+        .args([PG_USER, "-s", "/bin/sh", "-c", r#"exec "$0" "$@""#, "--"])
+        .arg(pg_bin(name));
+    command
+}
+
+fn free_port() -> Result<u16, InternalError> {
+    let listener = TcpListener::bind("localhost:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+fn run(command: &mut Command) -> Result<(), InternalError> {
+    let output = command.stdin(Stdio::null()).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(InternalError::message(format!(
+            "{:?} failed with {}: {}",
+            command.get_program(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Postgres runs detached from the tests, so a watchdog shell stops the cluster once
+/// the test process is gone, however it exited. The watchdog is in a process group of
+/// its own, so that interrupting the tests does not interrupt it.
+fn stop_on_exit(data: &Path, dir: &Path, as_root: bool) -> Result<(), InternalError> {
+    let stop = if as_root {
+        format!(r#"su {PG_USER} -s /bin/sh -c 'exec "$0" "$@"' -- "$0""#)
+    } else {
+        r#""$0""#.to_string()
+    };
+    let script = format!(
+        "while kill -0 {} 2>/dev/null; do sleep 1; done; {stop} -D \"$1\" -m immediate stop; rm -rf \"$2\"",
+        process::id()
+    );
+    Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg(pg_bin("pg_ctl"))
+        .args([OsStr::new(data), OsStr::new(dir)])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    Ok(())
+}
@@ -1,7 +1,8 @@
-use macros::format_uri;
-use sql::{encode_sql_identifier, sql};
-use sqlx::query::Query;
-use sqlx::{Execute, Postgres};
+use serde::Serialize;
+use sql::{encode_sql_identifier, sql, SqlExecute};
+use sqlx::Execute;
+use uri::{format_uri, match_uri, try_format_uri, uri, TemplateValue, TemplateVars, UriTemplate};
+use uuid::Uuid;
 
 #[test]
 fn test_uri_macro() {
@@ -12,6 +13,141 @@ fn test_uri_macro() {
     );
 }
 
+#[test]
+fn test_uri_macro_display_values() {
+    let id = Uuid::from_u128(1);
+    let page = 2;
+    let name = "a b".to_string();
+    assert_eq!(
+        format_uri!("http://localhost/things/{id}?page={page}&name={name}"),
+        "http://localhost/things/00000000-0000-0000-0000-000000000001?page=2&name=a%20b"
+    );
+}
+
+#[test]
+fn test_typed_uri_macro() {
+    let id = 42;
+    let uri = uri!("http://localhost/things/{id}").unwrap();
+    assert_eq!(uri.host(), Some("localhost"));
+    assert_eq!(uri.path(), "/things/42");
+
+    let host = "not a host";
+    assert!(uri!("http://{host:raw}/things").is_err());
+}
+
+#[test]
+fn test_match_uri_macro() {
+    let id = Uuid::from_u128(1);
+    let state = "a b&c";
+    let callback = uri!("http://localhost/things/{id}/callback?state={state:query_value}").unwrap();
+
+    let (id, state) = match_uri!(
+        "http://localhost/things/{id}/callback?state={state:query_value}",
+        callback
+    )
+    .unwrap();
+    assert_eq!(id, "00000000-0000-0000-0000-000000000001");
+    assert_eq!(state, "a b&c");
+
+    let template_matches = |uri| match_uri!("{host:raw}/things/{id}", uri);
+    assert_eq!(
+        template_matches("http://localhost/things/a%2Fb"),
+        Some(("http://localhost".to_string(), "a/b".to_string()))
+    );
+    assert_eq!(template_matches("http://localhost/things/a/b"), None);
+    let host = match_uri!("{host:raw}/things", "http://localhost/things");
+    assert_eq!(host.as_deref(), Some("http://localhost"));
+}
+
+#[test]
+fn test_uri_macro_query() {
+    #[derive(Serialize)]
+    struct Params {
+        name: Option<&'static str>,
+        id: Vec<u32>,
+    }
+    let params = Params {
+        name: Some("a b"),
+        id: vec![1, 2],
+    };
+    assert_eq!(
+        try_format_uri!("http://localhost/things{params:query}").unwrap(),
+        "http://localhost/things?name=a%20b&id=1&id=2"
+    );
+    let params = Params {
+        name: None,
+        id: vec![],
+    };
+    assert_eq!(
+        try_format_uri!("http://localhost/things{params:query}").unwrap(),
+        "http://localhost/things"
+    );
+    // The same type with or without a query
+    let id = 1;
+    assert_eq!(
+        try_format_uri!("http://localhost/things/{id}"),
+        Ok("http://localhost/things/1".to_string())
+    );
+}
+
+#[test]
+fn test_uri_macro_query_error() {
+    #[derive(Serialize)]
+    struct Params {
+        page: (u32, u32),
+        filter: Filter,
+    }
+    #[derive(Serialize)]
+    enum Filter {
+        Named(&'static str),
+    }
+    let params = Params {
+        page: (1, 2),
+        filter: Filter::Named("a"),
+    };
+    let error = try_format_uri!("http://localhost/things{params:query}#top").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid query parameters: enums with fields are not supported"
+    );
+}
+
+#[test]
+fn test_uri_macro_formats() {
+    let dir = "docs/2024 report";
+    let q = "a&b/c";
+    let f = "section 1";
+    assert_eq!(
+        format_uri!("http://localhost/files/{dir:path}?q={q:query_value}#{f:fragment}"),
+        "http://localhost/files/docs/2024%20report?q=a%26b/c#section%201"
+    );
+}
+
+#[test]
+fn test_uri_macro_escaped_braces() {
+    let name = "a b";
+    assert_eq!(
+        format_uri!("http://localhost/templates/{{name}}/{name}"),
+        "http://localhost/templates/{name}/a%20b"
+    );
+    assert_eq!(
+        match_uri!("/templates/{{name}}/{name}", "/templates/{name}/a%20b").as_deref(),
+        Some("a b")
+    );
+}
+
+#[test]
+fn test_runtime_uri_template() {
+    let template: UriTemplate = "http://localhost/things{/id}{?fields*}".parse().unwrap();
+    let vars = TemplateVars::new()
+        .set("id", Uuid::from_u128(1).to_string())
+        .set("fields", TemplateValue::list(["name", "description"]));
+    assert_eq!(
+        template.expand(&vars),
+        "http://localhost/things/00000000-0000-0000-0000-000000000001?fields=name&fields=description"
+    );
+}
+
 #[test]
 fn test_sql_encode() {
     assert_eq!(encode_sql_identifier("sp-1"), "\"sp-1\"");
@@ -20,13 +156,29 @@ fn test_sql_encode() {
 #[test]
 fn test_sql_macro() {
     let sp = "sp-1";
+    assert_eq!(sql!("SAVEPOINT ${sp:id}").sql(), "SAVEPOINT \"sp-1\"");
+    let sp_str = "sp-2".to_string();
+    assert_eq!(sql!("SAVEPOINT ${sp_str:id}").sql(), "SAVEPOINT \"sp-2\"");
+}
+
+#[test]
+fn test_sql_macro_bound_values() {
+    let id = Uuid::from_u128(1);
+    let name = "a'b";
+    let (sql, bound_values) = describe(sql!(
+        "UPDATE things SET name = ${name}, description = ${description} WHERE id = ${id}",
+        description = None::<String>
+    ));
     assert_eq!(
-        (sql!("SAVEPOINT ${sp:id}") as Query<Postgres, _>).sql(),
-        "SAVEPOINT \"sp-1\""
+        sql,
+        "UPDATE things SET name = $1, description = $2 WHERE id = $3"
     );
-    let sp_str = "sp-2".to_string();
     assert_eq!(
-        (sql!("SAVEPOINT ${sp_str:id}") as Query<Postgres, _>).sql(),
-        "SAVEPOINT \"sp-2\""
+        bound_values,
+        [r#""a'b""#, "None", "00000000-0000-0000-0000-000000000001"]
     );
 }
+
+fn describe<'q>(query: impl SqlExecute<'q>) -> (String, Vec<String>) {
+    (query.sql().to_string(), query.bound_values().to_vec())
+}
@@ -0,0 +1,349 @@
+use std::any::{type_name, Any};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_core::Stream;
+use futures_util::{stream, StreamExt};
+use sqlx::postgres::{PgQueryResult, PgRow};
+use sqlx::FromRow;
+
+use http_client::PropagatedHeaders;
+use sql::SqlExecute;
+
+use crate::context::{Context, ContextHooks, Environment, RequestTrace, Transactional};
+use crate::db::{
+    copy_statement, CopyFormat, DatabaseAccess, DatabaseReadAccess, LockKey, SessionSettings,
+};
+use crate::error::InternalError;
+use crate::tests::{test_config_with_database_url, EXTERNAL_DATABASE_URL};
+
+/// A statement that was run against a [`MockDatabase`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExecutedQuery {
+    /// SQL with whitespace collapsed, as used for matching responses
+    pub sql: String,
+    /// The `Debug` representation of each bound value. Only queries built with `sql!`
+    /// keep their values, so this is empty for plain sqlx queries.
+    pub bind_values: Vec<String>,
+}
+
+type RowFactory = Box<dyn Fn() -> Box<dyn Any + Send> + Send + Sync>;
+
+enum MockOutcome {
+    Rows {
+        type_name: &'static str,
+        rows: RowFactory,
+    },
+    Error(String),
+}
+
+struct MockResponse {
+    pattern: String,
+    outcome: MockOutcome,
+}
+
+enum MockAnswer {
+    Empty,
+    Rows(&'static str, Box<dyn Any + Send>),
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: Vec<MockResponse>,
+    queries: Vec<ExecutedQuery>,
+}
+
+/// In-memory stand-in for the database that records every statement run against it
+/// and answers with canned responses. A response applies to every statement whose SQL
+/// contains its pattern (compared with whitespace collapsed), and the first matching
+/// response wins. Statements without a response return no rows.
+///
+/// Clones share the same state, so a test can keep a handle to inspect the queries.
+#[derive(Clone, Default)]
+pub struct MockDatabase {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers matching statements with the rows. Postgres rows can't be constructed
+    /// outside sqlx, so the rows are typed and must be fetched as `T`.
+    pub fn returns<T: Clone + Send + Sync + 'static>(&self, pattern: &str, rows: Vec<T>) {
+        self.respond(
+            pattern,
+            MockOutcome::Rows {
+                type_name: type_name::<T>(),
+                rows: Box::new(move || Box::new(rows.clone())),
+            },
+        );
+    }
+
+    /// Fails matching statements with the error message
+    pub fn fails(&self, pattern: &str, message: &str) {
+        self.respond(pattern, MockOutcome::Error(message.to_string()));
+    }
+
+    /// All statements run so far, including `BEGIN`, `COMMIT` and `ROLLBACK` from a [`MockContext`]
+    pub fn queries(&self) -> Vec<ExecutedQuery> {
+        self.state.lock().unwrap().queries.clone()
+    }
+
+    fn respond(&self, pattern: &str, outcome: MockOutcome) {
+        self.state.lock().unwrap().responses.push(MockResponse {
+            pattern: collapse_whitespace(pattern),
+            outcome,
+        });
+    }
+
+    fn run(&self, sql: &str, bind_values: Vec<String>) -> Result<MockAnswer, InternalError> {
+        let sql = collapse_whitespace(sql);
+        let mut state = self.state.lock().unwrap();
+        let answer = match state.responses.iter().find(|r| sql.contains(&r.pattern)) {
+            None => Ok(MockAnswer::Empty),
+            Some(MockResponse {
+                outcome: MockOutcome::Rows { type_name, rows },
+                ..
+            }) => Ok(MockAnswer::Rows(type_name, rows())),
+            Some(MockResponse {
+                outcome: MockOutcome::Error(message),
+                ..
+            }) => Err(InternalError::message(message.clone())),
+        };
+        state.queries.push(ExecutedQuery { sql, bind_values });
+        answer
+    }
+
+    fn run_query<'q>(&self, mut query: impl SqlExecute<'q>) -> Result<MockAnswer, InternalError> {
+        let bind_values = query.bound_values().to_vec();
+        // Encoded like they would be for the database, so that encoding errors surface
+        query
+            .take_arguments()
+            .map_err(|e| InternalError::message(e.to_string()))?;
+        self.run(query.sql(), bind_values)
+    }
+}
+
+fn collapse_whitespace(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl DatabaseReadAccess for MockDatabase {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        let rows = self.run_query(query).and_then(|answer| match answer {
+            MockAnswer::Empty => Ok(vec![]),
+            MockAnswer::Rows(type_name, _) => Err(InternalError::message(format!(
+                "Mock rows of {type_name} can only be read with fetch_all, fetch_one or fetch_optional"
+            ))),
+        });
+        Box::pin(async move { rows })
+    }
+
+    fn fetch_all<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow> + 'static>(
+        &'e mut self,
+        query: impl SqlExecute<'q> + 'q,
+    ) -> BoxFuture<'e, Result<Vec<T>, InternalError>> {
+        let answer = self.run_query(query);
+        Box::pin(async move {
+            match answer? {
+                MockAnswer::Empty => Ok(vec![]),
+                MockAnswer::Rows(rows_type, rows) => {
+                    rows.downcast::<Vec<T>>().map(|rows| *rows).map_err(|_| {
+                        InternalError::message(format!(
+                            "Mock rows of {rows_type} were fetched as {}",
+                            type_name::<T>()
+                        ))
+                    })
+                }
+            }
+        })
+    }
+}
+
+impl DatabaseAccess for MockDatabase {
+    fn execute<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>> {
+        // The number of affected rows can't be set outside sqlx, so it is always zero
+        let result = self.run_query(query).map(|_| PgQueryResult::default());
+        Box::pin(async move { result })
+    }
+
+    fn copy_in<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+        data: impl Stream<Item = Result<Bytes, InternalError>> + Send + 'e,
+    ) -> BoxFuture<'e, Result<u64, InternalError>> {
+        let answer = self.run(
+            &copy_statement(table, columns, format, "FROM STDIN"),
+            vec![],
+        );
+        Box::pin(async move {
+            answer?;
+            let mut data = std::pin::pin!(data);
+            while let Some(chunk) = data.next().await {
+                chunk?;
+            }
+            Ok(0)
+        })
+    }
+
+    fn copy_out<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+    ) -> BoxFuture<'e, Result<BoxStream<'e, Result<Bytes, InternalError>>, InternalError>> {
+        let answer = self.run(&copy_statement(table, columns, format, "TO STDOUT"), vec![]);
+        Box::pin(async move {
+            answer?;
+            Ok(stream::empty().boxed())
+        })
+    }
+
+    fn advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+        _timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), InternalError>> {
+        let answer = self.run(ADVISORY_XACT_LOCK_SQL, vec![format!("{:?}", key.into().0)]);
+        Box::pin(async move { answer.map(|_| ()) })
+    }
+}
+
+/// Recorded for each advisory lock that is waited for, like the statement that takes it
+const ADVISORY_XACT_LOCK_SQL: &str = "SELECT pg_advisory_xact_lock($1)";
+
+/// Recorded for each session setting, like the query of a real transaction
+const SET_CONFIG_SQL: &str = "SELECT set_config($1, $2, true)";
+
+/// Context backed by a [`MockDatabase`], usable both as a root context and as a
+/// transaction. Beginning, committing and rolling back are recorded as `BEGIN`,
+/// `COMMIT` and `ROLLBACK` statements, so they can be made to fail with
+/// [`MockDatabase::fails`]. Transaction hooks are run like in a real transaction;
+/// transactions begun from a context made with [`MockContext::new`] are outermost ones,
+/// like those begun from a root context.
+///
+/// The environment is never connected to the database, so it doesn't matter that its
+/// URL may not point to a running server.
+pub struct MockContext<'a> {
+    env: Environment,
+    trace: RequestTrace,
+    db: MockDatabase,
+    root: bool,
+    hooks: ContextHooks<'a>,
+    session_settings: SessionSettings,
+}
+
+impl MockContext<'_> {
+    pub fn new(db: &MockDatabase) -> Self {
+        let env = Environment::init_lazy_with_config(
+            test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap(),
+        )
+        .unwrap();
+        MockContext {
+            env,
+            trace: RequestTrace::new(),
+            db: db.clone(),
+            root: true,
+            hooks: ContextHooks::outermost(),
+            session_settings: SessionSettings::new(),
+        }
+    }
+}
+
+impl PropagatedHeaders for MockContext<'_> {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.trace.propagated_headers()
+    }
+}
+
+impl Context for MockContext<'_> {
+    fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    fn db(&mut self) -> &mut impl DatabaseReadAccess {
+        &mut self.db
+    }
+
+    async fn begin(&mut self) -> Result<impl Context + Transactional, InternalError> {
+        self.db.run("BEGIN", vec![])?;
+        if self.root {
+            for (name, value) in &self.session_settings {
+                self.db.run(
+                    SET_CONFIG_SQL,
+                    vec![format!("{name:?}"), format!("{value:?}")],
+                )?;
+            }
+        }
+        Ok(MockContext {
+            env: self.env.clone(),
+            trace: self.trace.clone(),
+            db: self.db.clone(),
+            root: false,
+            hooks: if self.root {
+                ContextHooks::outermost()
+            } else {
+                ContextHooks::savepoint(&mut self.hooks)
+            },
+            session_settings: self.session_settings.clone(),
+        })
+    }
+
+    async fn set_session_setting(&mut self, name: &str, value: &str) -> Result<(), InternalError> {
+        if !self.root {
+            self.db.run(
+                SET_CONFIG_SQL,
+                vec![format!("{name:?}"), format!("{value:?}")],
+            )?;
+        }
+        self.session_settings
+            .insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+impl Transactional for MockContext<'_> {
+    async fn commit(self) -> Result<(), InternalError> {
+        match self.db.run("COMMIT", vec![]) {
+            Ok(_) => {
+                self.hooks.committed();
+                Ok(())
+            }
+            Err(e) => {
+                self.hooks.rolled_back();
+                Err(e)
+            }
+        }
+    }
+
+    async fn rollback(self) -> Result<(), InternalError> {
+        let result = self.db.run("ROLLBACK", vec![]);
+        self.hooks.rolled_back();
+        result.map(|_| ())
+    }
+
+    fn db_mut(&mut self) -> &mut impl DatabaseAccess {
+        &mut self.db
+    }
+
+    fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_commit(hook);
+    }
+
+    fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.hooks.on_rollback(hook);
+    }
+}
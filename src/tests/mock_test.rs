@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use tokio::test;
+use uuid::Uuid;
+
+use crate::context::{Context, Transactional};
+use crate::db::DbThing;
+use crate::service::{add_new_thing, delete_thing};
+use crate::tests::{ExecutedQuery, MockContext, MockDatabase, ThingFactory};
+
+#[test]
+pub async fn test_add_new_thing_inserts_thing() {
+    let db = MockDatabase::new();
+    let thing = DbThing {
+        id: Uuid::from_u128(1),
+        name: "thingy".to_string(),
+        description: Some("This is the real deal".to_string()),
+        created_at: Utc::now(),
+    };
+    db.returns("INSERT INTO things", vec![thing.clone()]);
+
+    let mut ctx = MockContext::new(&db);
+    let data = ThingFactory::new()
+        .name("thingy")
+        .description(Some("This is the real deal"))
+        .build_data();
+    let added = add_new_thing(&mut ctx, data).await.unwrap();
+
+    assert_eq!(added, thing);
+    assert_eq!(
+        db.queries(),
+        vec![ExecutedQuery {
+            sql: "INSERT INTO things (name, description) VALUES ($1, $2) RETURNING *".to_string(),
+            bind_values: vec![
+                r#""thingy""#.to_string(),
+                r#"Some("This is the real deal")"#.to_string()
+            ],
+        }]
+    );
+}
+
+#[test]
+pub async fn test_add_new_thing_fails_when_insert_fails() {
+    let db = MockDatabase::new();
+    db.fails("INSERT INTO things", "duplicate key value");
+
+    let mut ctx = MockContext::new(&db);
+    let err = add_new_thing(&mut ctx, ThingFactory::new().build_data())
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Error: duplicate key value");
+    let queries = db.queries();
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].bind_values[0], r#""thing-1""#);
+}
+
+#[test]
+pub async fn test_add_new_thing_fails_when_insert_returns_nothing() {
+    let db = MockDatabase::new();
+
+    let mut ctx = MockContext::new(&db);
+    let err = add_new_thing(&mut ctx, ThingFactory::new().build_data())
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Error: No results for query, expected exactly one result"
+    );
+}
+
+#[test]
+pub async fn test_delete_thing() {
+    let db = MockDatabase::new();
+    let mut ctx = MockContext::new(&db);
+    delete_thing(&mut ctx, Uuid::from_u128(1)).await.unwrap();
+
+    assert_eq!(
+        db.queries(),
+        vec![ExecutedQuery {
+            sql: "DELETE FROM things WHERE id=$1".to_string(),
+            bind_values: vec!["00000000-0000-0000-0000-000000000001".to_string()],
+        }]
+    );
+}
+
+#[test]
+pub async fn test_delete_thing_fails_when_delete_fails() {
+    let db = MockDatabase::new();
+    db.fails("DELETE FROM things", "connection reset");
+
+    let mut ctx = MockContext::new(&db);
+    let err = delete_thing(&mut ctx, Uuid::from_u128(1))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Error: connection reset");
+}
+
+#[test]
+pub async fn test_mock_rows_of_wrong_type_are_an_error() {
+    let db = MockDatabase::new();
+    db.returns("INSERT INTO things", vec![(1i64,)]);
+
+    let mut ctx = MockContext::new(&db);
+    let err = add_new_thing(&mut ctx, ThingFactory::new().build_data())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("were fetched as"), "{err}");
+}
+
+#[test]
+pub async fn test_failed_mock_commit_runs_rollback_hooks() {
+    let db = MockDatabase::new();
+    db.fails("COMMIT", "serialization failure");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let mut ctx = MockContext::new(&db);
+    let mut tx = ctx.begin().await.unwrap();
+    delete_thing(&mut tx, Uuid::from_u128(1)).await.unwrap();
+    let commit_events = events.clone();
+    tx.on_commit(move || commit_events.lock().unwrap().push("commit"));
+    let rollback_events = events.clone();
+    tx.on_rollback(move || rollback_events.lock().unwrap().push("rollback"));
+    let err = tx.commit().await.unwrap_err();
+
+    assert_eq!(err.to_string(), "Error: serialization failure");
+    assert_eq!(*events.lock().unwrap(), vec!["rollback"]);
+    let statements = db.queries().into_iter().map(|q| q.sql).collect::<Vec<_>>();
+    assert_eq!(
+        statements,
+        vec!["BEGIN", "DELETE FROM things WHERE id=$1", "COMMIT"]
+    );
+}
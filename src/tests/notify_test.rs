@@ -0,0 +1,124 @@
+use std::pin::pin;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::test;
+use tokio::time::timeout;
+
+use sql::sql;
+
+use crate::context::{Context, Transactional};
+use crate::db::{DatabaseAccess, DatabaseReadAccess};
+use crate::tests::TestEnvironment;
+
+#[test]
+pub async fn test_notification_is_delivered_on_commit() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let notifications = env.env.listen::<Value>(&["things"]).await.unwrap();
+    let mut notifications = pin!(notifications);
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.db_mut()
+        .notify("things", &json!({ "id": 1 }))
+        .await
+        .unwrap();
+    assert!(timeout(Duration::from_millis(200), notifications.next())
+        .await
+        .is_err());
+    tx.commit().await.unwrap();
+
+    let received = timeout(Duration::from_secs(5), notifications.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.channel, "things");
+    assert_eq!(received.payload, json!({ "id": 1 }));
+}
+
+#[test]
+pub async fn test_notification_is_discarded_on_rollback() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let notifications = env.env.listen::<i32>(&["numbers"]).await.unwrap();
+    let mut notifications = pin!(notifications);
+
+    let mut tx = ctx.begin().await.unwrap();
+    tx.db_mut().notify("numbers", &1).await.unwrap();
+    tx.rollback().await.unwrap();
+    let mut tx = ctx.begin().await.unwrap();
+    tx.db_mut().notify("numbers", &2).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let received = timeout(Duration::from_secs(5), notifications.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.payload, 2);
+}
+
+#[test]
+pub async fn test_listener_subscribes_again_after_connection_loss() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let notifications = env.env.listen::<i32>(&["reconnect"]).await.unwrap();
+    let mut notifications = pin!(notifications);
+
+    let mut tx = ctx.begin().await.unwrap();
+    let terminated = tx
+        .db_mut()
+        .fetch_all::<(bool,)>(sql!(
+            // language=postgresql
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+             WHERE query = 'LISTEN \"reconnect\";' AND datname = current_database()"
+        ))
+        .await
+        .unwrap();
+    assert!(terminated.contains(&(true,)));
+    tx.commit().await.unwrap();
+
+    // Notifications sent before the listener has reconnected are lost, so keep sending
+    let received = timeout(Duration::from_secs(10), async {
+        loop {
+            env.env
+                .db_connection()
+                .await
+                .unwrap()
+                .notify("reconnect", &1)
+                .await
+                .unwrap();
+            if let Ok(Some(n)) = timeout(Duration::from_millis(200), notifications.next()).await {
+                return n;
+            }
+        }
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(received.payload, 1);
+}
+
+#[test]
+pub async fn test_notifications_end_when_pool_is_closed() {
+    let env = TestEnvironment::init().await;
+    let notifications = env.env.listen::<i32>(&["closing"]).await.unwrap();
+    let mut notifications = pin!(notifications);
+
+    // Closing waits for the connection of the listener, which is returned when the
+    // stream ends
+    let closing = tokio::spawn({
+        let env = env.env.clone();
+        async move { env.close().await }
+    });
+    let next = timeout(Duration::from_secs(5), notifications.next())
+        .await
+        .unwrap();
+    assert!(next.is_none());
+    timeout(Duration::from_secs(5), closing)
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,60 @@
+use http::{header, StatusCode};
+use serde_json::Value;
+use tokio::test;
+use utoipa::OpenApi;
+
+use crate::app::create_app;
+use crate::app::openapi::ApiDoc;
+use crate::app::routes::api_routes;
+use crate::context::Environment;
+use crate::tests::{test_config_with_database_url, TestApp, EXTERNAL_DATABASE_URL};
+
+fn app() -> TestApp {
+    let config = test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap();
+    let env = Environment::init_lazy_with_config(config).unwrap();
+    TestApp::from_router(create_app(env))
+}
+
+#[test]
+pub async fn test_every_route_is_documented() {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+    for (method, path) in api_routes().routes() {
+        let operation = &spec["paths"][path][method.as_str().to_lowercase()];
+        assert!(
+            operation.is_object(),
+            "{method} {path} is missing from the OpenAPI document"
+        );
+    }
+}
+
+#[test]
+pub async fn test_get_openapi_json() {
+    let res = app().get("/openapi.json").await;
+    res.assert_status(StatusCode::OK);
+    let spec: Value = res.json();
+
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3.1"));
+    let parameter = &spec["paths"]["/things/{thing_id}"]["get"]["parameters"][0];
+    assert_eq!(parameter["name"], "thing_id");
+    assert_eq!(parameter["in"], "path");
+    assert_eq!(parameter["schema"]["type"], "string");
+    assert_eq!(parameter["schema"]["format"], "uuid");
+    let schemas = &spec["components"]["schemas"];
+    assert_eq!(
+        schemas["ApiErrorCode"]["enum"],
+        serde_json::json!(["not_found", "invalid_path_param", "internal_server_error"])
+    );
+    assert!(schemas["ApiThing"].is_object());
+    assert!(schemas["ApiThingData"].is_object());
+}
+
+#[test]
+pub async fn test_get_docs() {
+    let res = app().get("/docs/").await;
+    res.assert_status(StatusCode::OK);
+    assert!(res.headers[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use tokio::test;
+
+use sql::sql;
+
+use crate::context::{Context, Transactional};
+use crate::db::{DatabaseAccess, DatabaseReadAccess};
+use crate::tests::{test_config, TestEnvironment};
+
+#[test]
+pub async fn test_pinned_context_keeps_session_state() {
+    let env = init_pinned().await;
+    let mut ctx = env.ctx().await;
+    let pid = backend_pid(&mut ctx).await;
+    set_setting(&mut ctx, "pinned").await;
+    assert_eq!(get_setting(&mut ctx).await, "pinned");
+    assert_eq!(backend_pid(&mut ctx).await, pid);
+
+    let mut tx = ctx.begin().await.unwrap();
+    assert_eq!(backend_pid(&mut tx).await, pid);
+    assert_eq!(get_setting(&mut tx).await, "pinned");
+    tx.db_mut()
+        .execute(sql!("CREATE TABLE foo (value INTEGER)"))
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(backend_pid(&mut ctx).await, pid);
+}
+
+#[test]
+pub async fn test_pinned_context_reads_are_read_only() {
+    let env = init_pinned().await;
+    let mut ctx = env.ctx().await;
+    let res = ctx
+        .db()
+        .fetch_all::<(i32,)>(sql!("CREATE TABLE foo (value INTEGER)"))
+        .await;
+    assert!(res.is_err());
+}
+
+#[test]
+pub async fn test_pinned_connection_is_reset_on_release() {
+    let env = init_pinned().await;
+    let mut ctx = env.ctx().await;
+    let pid = backend_pid(&mut ctx).await;
+    set_setting(&mut ctx, "pinned").await;
+    drop(ctx);
+    // Connection is reset in the background before it returns to the pool
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Hold on to the contexts so that each one gets a different connection from the pool
+    let mut contexts = vec![];
+    for _ in 0..5 {
+        let mut ctx = env.ctx().await;
+        if backend_pid(&mut ctx).await == pid {
+            assert_eq!(get_setting(&mut ctx).await, "");
+            assert_eq!(read_only_setting(&mut ctx).await, "on");
+            return;
+        }
+        contexts.push(ctx);
+    }
+    panic!("Pinned connection was not returned to the pool");
+}
+
+async fn read_only_setting(ctx: &mut impl Context) -> String {
+    ctx.db()
+        .fetch_one::<(String,)>(sql!("SHOW default_transaction_read_only"))
+        .await
+        .unwrap()
+        .0
+}
+
+async fn init_pinned() -> TestEnvironment {
+    let mut config = test_config().unwrap();
+    config.database.pin_request_connection = true;
+    TestEnvironment::init_with_config(config).await
+}
+
+async fn backend_pid(ctx: &mut impl Context) -> i32 {
+    ctx.db()
+        .fetch_one::<(i32,)>(sql!("SELECT pg_backend_pid()"))
+        .await
+        .unwrap()
+        .0
+}
+
+async fn set_setting(ctx: &mut impl Context, value: &str) {
+    ctx.db()
+        .fetch_one::<(String,)>(sql!(
+            // language=postgresql
+            "SELECT set_config('app.test_value', ${value}, false)"
+        ))
+        .await
+        .unwrap();
+}
+
+async fn get_setting(ctx: &mut impl Context) -> String {
+    ctx.db()
+        .fetch_one::<(Option<String>,)>(sql!(
+            // language=postgresql
+            "SELECT current_setting('app.test_value', true)"
+        ))
+        .await
+        .unwrap()
+        .0
+        .unwrap_or_default()
+}
@@ -0,0 +1,232 @@
+use http_client::PropagatedHeaders;
+use sql::SqlExecute;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_core::Stream;
+use sqlx::postgres::{PgQueryResult, PgRow};
+use sqlx::FromRow;
+
+use crate::context::{Context, Environment, Transactional};
+use crate::db::{copy_statement, CopyFormat, DatabaseAccess, DatabaseReadAccess, LockKey};
+use crate::error::InternalError;
+
+/// Runs the block with the context wrapped in a [`CountingContext`] and fails with
+/// the executed SQL if the block did not run exactly `count` statements. Statements
+/// run in transactions begun inside the block are counted as well.
+///
+/// ```ignore
+/// let thing = assert_queries!(tx, 1, {
+///     add_new_thing(&mut tx, data).await.unwrap()
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_queries {
+    ($ctx:ident, $count:expr, $body:block) => {{
+        let log = $crate::tests::QueryLog::default();
+        let result = {
+            #[allow(unused_mut)]
+            let mut $ctx = $crate::tests::CountingContext::borrowed(&mut $ctx, log.clone());
+            $body
+        };
+        log.assert_count($count);
+        result
+    }};
+}
+
+/// The SQL of the statements executed through a [`CountingContext`]. Clones share the log.
+#[derive(Debug, Clone, Default)]
+pub struct QueryLog(Arc<Mutex<Vec<String>>>);
+
+impl QueryLog {
+    pub fn queries(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    #[track_caller]
+    pub fn assert_count(&self, count: usize) {
+        let queries = self.queries();
+        if queries.len() != count {
+            let list = queries
+                .iter()
+                .enumerate()
+                .map(|(i, sql)| format!("{}. {sql}", i + 1))
+                .collect::<Vec<_>>()
+                .join("\n");
+            panic!(
+                "Expected {count} queries, but {} were executed:\n{list}",
+                queries.len()
+            );
+        }
+    }
+
+    fn record(&self, sql: &str) {
+        let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.0.lock().unwrap().push(sql);
+    }
+}
+
+enum Inner<'a, C> {
+    Owned(C),
+    Borrowed(&'a mut C),
+}
+
+impl<C> Deref for Inner<'_, C> {
+    type Target = C;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Inner::Owned(ctx) => ctx,
+            Inner::Borrowed(ctx) => ctx,
+        }
+    }
+}
+
+impl<C> DerefMut for Inner<'_, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Inner::Owned(ctx) => ctx,
+            Inner::Borrowed(ctx) => ctx,
+        }
+    }
+}
+
+/// Context that records every statement run through its database access in a
+/// [`QueryLog`] before passing it on to the wrapped context. Transaction control
+/// statements are not recorded.
+///
+/// A borrowed context can't be committed or rolled back through the wrapper; only
+/// transactions begun from the wrapper can.
+pub struct CountingContext<'a, C> {
+    inner: Inner<'a, C>,
+    log: QueryLog,
+}
+
+impl<'a, C: Context> CountingContext<'a, C> {
+    pub fn borrowed(ctx: &'a mut C, log: QueryLog) -> Self {
+        CountingContext {
+            inner: Inner::Borrowed(ctx),
+            log,
+        }
+    }
+}
+
+impl<C: Context> PropagatedHeaders for CountingContext<'_, C> {
+    fn propagated_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.inner.propagated_headers()
+    }
+}
+
+impl<C: Context> Context for CountingContext<'_, C> {
+    fn env(&self) -> &Environment {
+        self.inner.env()
+    }
+
+    fn db(&mut self) -> &mut impl DatabaseReadAccess {
+        self
+    }
+
+    async fn begin(&mut self) -> Result<impl Context + Transactional, InternalError> {
+        let tx = self.inner.begin().await?;
+        Ok(CountingContext {
+            inner: Inner::Owned(tx),
+            log: self.log.clone(),
+        })
+    }
+
+    async fn set_session_setting(&mut self, name: &str, value: &str) -> Result<(), InternalError> {
+        self.inner.set_session_setting(name, value).await
+    }
+}
+
+impl<C: Context + Transactional> Transactional for CountingContext<'_, C> {
+    async fn commit(self) -> Result<(), InternalError> {
+        match self.inner {
+            Inner::Owned(ctx) => ctx.commit().await,
+            Inner::Borrowed(_) => panic!("A borrowed context can't be committed"),
+        }
+    }
+
+    async fn rollback(self) -> Result<(), InternalError> {
+        match self.inner {
+            Inner::Owned(ctx) => ctx.rollback().await,
+            Inner::Borrowed(_) => panic!("A borrowed context can't be rolled back"),
+        }
+    }
+
+    fn db_mut(&mut self) -> &mut impl DatabaseAccess {
+        self
+    }
+
+    fn on_commit(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.inner.on_commit(hook);
+    }
+
+    fn on_rollback(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        self.inner.on_rollback(hook);
+    }
+}
+
+impl<C: Context> DatabaseReadAccess for CountingContext<'_, C> {
+    fn fetch_rows<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<PgRow>, InternalError>> {
+        self.log.record(query.sql());
+        self.inner.db().fetch_rows(query)
+    }
+
+    // Passed on as a whole, so that a database that doesn't return raw rows still works
+    fn fetch_all<'e, 'q: 'e, T: for<'r> FromRow<'r, PgRow> + 'static>(
+        &'e mut self,
+        query: impl SqlExecute<'q> + 'q,
+    ) -> BoxFuture<'e, Result<Vec<T>, InternalError>> {
+        self.log.record(query.sql());
+        self.inner.db().fetch_all(query)
+    }
+}
+
+impl<C: Context + Transactional> DatabaseAccess for CountingContext<'_, C> {
+    fn execute<'e, 'q: 'e, E: 'q + SqlExecute<'q>>(
+        &'e mut self,
+        query: E,
+    ) -> BoxFuture<'e, Result<PgQueryResult, InternalError>> {
+        self.log.record(query.sql());
+        self.inner.db_mut().execute(query)
+    }
+
+    fn copy_in<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+        data: impl Stream<Item = Result<Bytes, InternalError>> + Send + 'e,
+    ) -> BoxFuture<'e, Result<u64, InternalError>> {
+        self.log
+            .record(&copy_statement(table, columns, format, "FROM STDIN"));
+        self.inner.db_mut().copy_in(table, columns, format, data)
+    }
+
+    fn copy_out<'e>(
+        &'e mut self,
+        table: &'e str,
+        columns: &'e [&'e str],
+        format: CopyFormat,
+    ) -> BoxFuture<'e, Result<BoxStream<'e, Result<Bytes, InternalError>>, InternalError>> {
+        self.log
+            .record(&copy_statement(table, columns, format, "TO STDOUT"));
+        self.inner.db_mut().copy_out(table, columns, format)
+    }
+
+    fn advisory_xact_lock(
+        &mut self,
+        key: impl Into<LockKey>,
+        timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), InternalError>> {
+        self.log.record("SELECT pg_advisory_xact_lock($1)");
+        self.inner.db_mut().advisory_xact_lock(key, timeout)
+    }
+}
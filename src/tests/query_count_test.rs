@@ -0,0 +1,67 @@
+use tokio::test;
+
+use crate::assert_queries;
+use crate::context::{Context, Transactional};
+use crate::db::DbThing;
+use crate::service::{add_new_thing, delete_thing, find_thing};
+use crate::tests::{MockContext, MockDatabase, TestEnvironment, ThingFactory};
+
+#[test]
+pub async fn test_add_new_thing_runs_one_query() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+    let mut tx = ctx.begin().await.unwrap();
+
+    let thing = assert_queries!(tx, 1, {
+        add_new_thing(&mut tx, ThingFactory::new().build_data())
+            .await
+            .unwrap()
+    });
+    tx.commit().await.unwrap();
+
+    assert_eq!(thing.name, "thing-1");
+}
+
+#[test]
+pub async fn test_queries_in_nested_transactions_are_counted() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = env.ctx().await;
+
+    let thing = assert_queries!(ctx, 3, {
+        let mut tx = ctx.begin().await.unwrap();
+        let thing = add_new_thing(&mut tx, ThingFactory::new().build_data())
+            .await
+            .unwrap();
+        let mut nested = tx.begin().await.unwrap();
+        delete_thing(&mut nested, thing.id).await.unwrap();
+        nested.rollback().await.unwrap();
+        tx.commit().await.unwrap();
+        find_thing(&mut ctx, thing.id).await.unwrap()
+    });
+
+    assert!(thing.is_some());
+}
+
+#[test]
+#[should_panic(
+    expected = "Expected 2 queries, but 1 were executed:\n1. INSERT INTO things (name, description) VALUES ($1, $2) RETURNING *"
+)]
+pub async fn test_unexpected_query_count_lists_executed_queries() {
+    let db = MockDatabase::new();
+    db.returns(
+        "INSERT INTO things",
+        vec![DbThing {
+            id: Default::default(),
+            name: "thingy".to_string(),
+            description: None,
+            created_at: Default::default(),
+        }],
+    );
+    let mut ctx = MockContext::new(&db);
+
+    assert_queries!(ctx, 2, {
+        add_new_thing(&mut ctx, ThingFactory::new().build_data())
+            .await
+            .unwrap();
+    });
+}
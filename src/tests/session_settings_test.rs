@@ -0,0 +1,131 @@
+use tokio::test;
+
+use sql::sql;
+
+use crate::context::{Context, RootContext, Transactional};
+use crate::db::DatabaseReadAccess;
+use crate::tests::{test_config, MockContext, MockDatabase, TestEnvironment};
+
+#[test]
+pub async fn test_session_settings_are_applied_to_transactions() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = RootContext::new(env.env.clone());
+    ctx.set_session_setting("app.user_id", "user-1")
+        .await
+        .unwrap();
+    ctx.set_session_setting("app.tenant_id", "tenant'; DROP TABLE things; --")
+        .await
+        .unwrap();
+
+    let mut tx = ctx.begin().await.unwrap();
+    assert_eq!(
+        setting(&mut tx, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    assert_eq!(
+        setting(&mut tx, "app.tenant_id").await,
+        Some("tenant'; DROP TABLE things; --".to_string())
+    );
+
+    let mut nested = tx.begin().await.unwrap();
+    assert_eq!(
+        setting(&mut nested, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    nested.rollback().await.unwrap();
+
+    assert_eq!(
+        setting(&mut tx, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    tx.commit().await.unwrap();
+}
+
+#[test]
+pub async fn test_session_settings_are_local_to_transaction() {
+    let mut config = test_config().unwrap();
+    config.database.pin_request_connection = true;
+    let env = TestEnvironment::init_with_config(config).await;
+    let mut ctx = RootContext::new(env.env.clone());
+    ctx.set_session_setting("app.user_id", "user-1")
+        .await
+        .unwrap();
+
+    let mut tx = ctx.begin().await.unwrap();
+    assert_eq!(
+        setting(&mut tx, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    tx.commit().await.unwrap();
+
+    // Same connection, but the setting ended with the transaction
+    let value = setting(&mut ctx, "app.user_id").await;
+    assert_eq!(value.unwrap_or_default(), "");
+}
+
+#[test]
+pub async fn test_session_settings_are_set_inside_transactions() {
+    let env = TestEnvironment::init().await;
+    let mut ctx = RootContext::new(env.env.clone());
+
+    let mut tx = ctx.begin().await.unwrap();
+    set_user(&mut tx, "user-1").await;
+    assert_eq!(
+        setting(&mut tx, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    let mut nested = tx.begin().await.unwrap();
+    set_user(&mut nested, "user-2").await;
+    assert_eq!(
+        setting(&mut nested, "app.user_id").await,
+        Some("user-2".to_string())
+    );
+    nested.rollback().await.unwrap();
+
+    // Like other changes, the setting of the savepoint is rolled back with it
+    assert_eq!(
+        setting(&mut tx, "app.user_id").await,
+        Some("user-1".to_string())
+    );
+    tx.commit().await.unwrap();
+}
+
+#[test]
+pub async fn test_mock_context_records_session_settings() {
+    let db = MockDatabase::new();
+    let mut ctx = MockContext::new(&db);
+    set_user(&mut ctx, "user-1").await;
+
+    let mut tx = ctx.begin().await.unwrap();
+    set_user(&mut tx, "user-2").await;
+    tx.commit().await.unwrap();
+
+    let statements = db.queries().into_iter().map(|q| q.sql).collect::<Vec<_>>();
+    assert_eq!(
+        statements,
+        vec![
+            "BEGIN",
+            "SELECT set_config($1, $2, true)",
+            "SELECT set_config($1, $2, true)",
+            "COMMIT"
+        ]
+    );
+}
+
+/// Generic over the context, like a service would be
+async fn set_user(ctx: &mut impl Context, user_id: &str) {
+    ctx.set_session_setting("app.user_id", user_id)
+        .await
+        .unwrap();
+}
+
+async fn setting(ctx: &mut impl Context, name: &str) -> Option<String> {
+    ctx.db()
+        .fetch_one::<(Option<String>,)>(sql!(
+            // language=postgresql
+            "SELECT current_setting(${name}, true)"
+        ))
+        .await
+        .unwrap()
+        .0
+}
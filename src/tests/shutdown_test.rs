@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use axum::routing::get;
+use axum::Router;
+use http::StatusCode;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio::test;
+
+use crate::app::{add_layers, close_environment, serve, ShutdownSummary};
+use crate::context::{Environment, Shutdown};
+use crate::error::InternalError;
+use crate::tests::{test_config_with_database_url, TestEnvironment, EXTERNAL_DATABASE_URL};
+
+fn env() -> Environment {
+    let config = test_config_with_database_url(EXTERNAL_DATABASE_URL).unwrap();
+    Environment::init_lazy_with_config(config).unwrap()
+}
+
+/// Serves a route that answers after the delay
+async fn start(
+    env: &Environment,
+    delay: Duration,
+    timeout: Duration,
+) -> (String, JoinHandle<Result<ShutdownSummary, InternalError>>) {
+    let routes = Router::new().route(
+        "/slow",
+        get(move || async move {
+            tokio::time::sleep(delay).await;
+            "done"
+        }),
+    );
+    let app = add_layers(routes, env.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let shutdown = env.shutdown.clone();
+    let server = tokio::spawn(async move { serve(listener, app, &shutdown, timeout).await });
+    (url, server)
+}
+
+async fn wait_for_in_flight(shutdown: &Shutdown, count: usize) {
+    while shutdown.in_flight() != count {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[test]
+pub async fn test_in_flight_requests_are_drained() {
+    let env = env();
+    let (url, server) = start(&env, Duration::from_millis(300), Duration::from_secs(5)).await;
+    let request = tokio::spawn(reqwest::get(format!("{url}/slow")));
+    wait_for_in_flight(&env.shutdown, 1).await;
+
+    assert!(env.shutdown.start());
+    assert!(!env.shutdown.start());
+    assert!(env.shutdown.is_shutting_down());
+    let res = request.await.unwrap().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "done");
+    let summary = server.await.unwrap().unwrap();
+    assert!(summary.is_clean());
+    assert_eq!(summary.completed, 1);
+    assert_eq!(summary.abandoned, 0);
+    assert!(reqwest::get(format!("{url}/slow")).await.is_err());
+}
+
+#[test]
+pub async fn test_shutdown_times_out() {
+    let env = env();
+    let (url, server) = start(&env, Duration::from_secs(10), Duration::from_millis(100)).await;
+    let _request = tokio::spawn(reqwest::get(format!("{url}/slow")));
+    wait_for_in_flight(&env.shutdown, 1).await;
+
+    env.shutdown.start();
+    let summary = server.await.unwrap().unwrap();
+    assert!(!summary.is_clean());
+    assert_eq!(summary.completed, 0);
+    assert_eq!(summary.abandoned, 1);
+    assert!(summary.drain_time >= Duration::from_millis(100));
+    assert!(summary.drain_time < Duration::from_secs(10));
+}
+
+#[test]
+pub async fn test_close_pools() {
+    let env = env();
+    env.close().await;
+    assert!(env.db_connection().await.is_err());
+}
+
+#[test]
+pub async fn test_close_pools_times_out_with_connections_in_use() {
+    let test_env = TestEnvironment::init().await;
+    let env = &test_env.env;
+    let conn = env.db_connection().await.unwrap();
+
+    let start = Instant::now();
+    assert!(!close_environment(env, Duration::from_millis(100)).await);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    drop(conn);
+    assert!(close_environment(env, Duration::from_secs(5)).await);
+}
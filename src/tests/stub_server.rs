@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::Router;
+use bytes::Bytes;
+use http::{HeaderMap, Method, Uri};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A request received by a [`StubServer`]
+#[derive(Debug, Clone)]
+pub struct StubRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Serves the router on a local port, for testing clients of other services or of the
+/// application itself. Every request is recorded before it is handled. The server stops
+/// when it is dropped.
+pub struct StubServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<StubRequest>>>,
+    task: JoinHandle<()>,
+}
+
+impl StubServer {
+    pub async fn start(router: Router) -> Self {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        let router = router.layer(middleware::from_fn(move |req: Request, next: Next| {
+            let recorded = recorded.clone();
+            async move { record(recorded, req, next).await }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let task = tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        StubServer {
+            url,
+            requests,
+            task,
+        }
+    }
+
+    pub fn requests(&self) -> Vec<StubRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn record(requests: Arc<Mutex<Vec<StubRequest>>>, req: Request, next: Next) -> Response {
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap();
+    requests.lock().unwrap().push(StubRequest {
+        method: parts.method.clone(),
+        uri: parts.uri.clone(),
+        headers: parts.headers.clone(),
+        body: body.clone(),
+    });
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
@@ -0,0 +1,108 @@
+use axum::body::{to_bytes, Body};
+use axum::Router;
+use bytes::Bytes;
+use http::{header, HeaderMap, Method, Request, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::app::create_app;
+use crate::tests::TestEnvironment;
+
+/// Sends requests through the application in-process, without binding a socket
+pub struct TestApp {
+    router: Router,
+}
+
+/// A buffered response of the [`TestApp`]
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestApp {
+    /// The full application, using the environment of the test
+    pub fn new(env: &TestEnvironment) -> Self {
+        Self::from_router(create_app(env.env.clone()))
+    }
+
+    pub fn from_router(router: Router) -> Self {
+        TestApp { router }
+    }
+
+    pub async fn request(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.send(Method::GET, uri, Body::empty()).await
+    }
+
+    pub async fn delete(&self, uri: &str) -> TestResponse {
+        self.send(Method::DELETE, uri, Body::empty()).await
+    }
+
+    pub async fn post_json(&self, uri: &str, body: &impl Serialize) -> TestResponse {
+        self.send_json(Method::POST, uri, body).await
+    }
+
+    pub async fn send_json(
+        &self,
+        method: Method,
+        uri: &str,
+        body: &impl Serialize,
+    ) -> TestResponse {
+        let body = serde_json::to_vec(body).unwrap();
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        self.request(request).await
+    }
+
+    async fn send(&self, method: Method, uri: &str, body: Body) -> TestResponse {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(body)
+            .unwrap();
+        self.request(request).await
+    }
+}
+
+impl TestResponse {
+    /// Panics with the body in the message if the status is not the expected one
+    #[track_caller]
+    pub fn assert_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(
+            self.status,
+            status,
+            "Unexpected status, body: {}",
+            self.text()
+        );
+        self
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// The body parsed as JSON, for example for checking it with `assert_json!`
+    #[track_caller]
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("Body is not JSON ({e}): {}", self.text()))
+    }
+}
@@ -0,0 +1,10 @@
+use rust_server::db::{DatabaseAccess, DatabasePool};
+use sql::sql;
+
+async fn rename_things(pool: &mut DatabasePool) {
+    pool.execute(sql!("UPDATE things SET name = 'renamed'"))
+        .await
+        .unwrap();
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use rust_server::context::Context;
+use rust_server::db::DatabaseAccess;
+use sql::sql;
+
+async fn rename_things(ctx: &impl Context) {
+    let mut pool = ctx.env().db_pool.clone();
+    pool.execute(sql!("UPDATE things SET name = 'renamed'"))
+        .await
+        .unwrap();
+}
+
+fn main() {}
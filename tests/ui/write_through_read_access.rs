@@ -0,0 +1,13 @@
+use rust_server::context::{Context, Environment, RootContext};
+use rust_server::db::DatabaseAccess;
+use sql::sql;
+
+async fn rename_things(env: Environment) {
+    let mut ctx = RootContext::new(env);
+    ctx.db()
+        .execute(sql!("UPDATE things SET name = 'renamed'"))
+        .await
+        .unwrap();
+}
+
+fn main() {}